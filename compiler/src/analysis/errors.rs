@@ -1,17 +1,112 @@
+use std::num::{ParseFloatError, ParseIntError};
+
+use parser::tokenizer::{Operator, Span};
+
 use super::SemanticType;
 
+#[derive(Debug, Clone)]
+pub enum LitParseError {
+    Int(ParseIntError),
+    Float(ParseFloatError),
+    OutOfRange(SemanticType),
+}
+
 #[derive(Debug, Clone)]
 pub enum SemanticError {
-    UndeclaredVariable(String),
-    UnrecognizedType(String),
-    FunctionRedeclare(String),
-    ProgramAnalysis,
+    UndeclaredVariable(String, Span),
+    UnrecognizedType(String, Span),
+    FunctionRedeclare(String, Span),
+    NestedFunction(String, Span),
+    AssignToImmutable(String, Span),
+    InvalidAssignment {
+        var_type: SemanticType,
+        expr_type: SemanticType,
+        span: Span,
+    },
+    NotCallable(String, Span),
+    InvalidArgCount {
+        function: String,
+        expected: usize,
+        received: usize,
+        span: Span,
+    },
+    InvalidArgType {
+        function: String,
+        expected: SemanticType,
+        received: SemanticType,
+        span: Span,
+    },
+    ProgramAnalysis(Span),
+    InvalidLiteral {
+        error: LitParseError,
+        span: Span,
+    },
     InvalidBinExpr {
         lhs_type: SemanticType,
         rhs_type: SemanticType,
+        span: Span,
+    },
+    InvalidOperator {
+        operator: Operator,
+        operand_type: SemanticType,
+        span: Span,
+    },
+    InvalidCast {
+        from: SemanticType,
+        to: SemanticType,
+        span: Span,
+    },
+    InvalidCondition(SemanticType, Span),
+    InvalidRange {
+        start_type: SemanticType,
+        end_type: SemanticType,
+        span: Span,
+    },
+    BreakOutsideLoop(Span),
+    ContinueOutsideLoop(Span),
+    ReturnOutsideFunction(Span),
+    InvalidReturnType {
+        expected: SemanticType,
+        received: SemanticType,
+        span: Span,
+    },
+    MismatchedBranches {
+        then_type: SemanticType,
+        else_type: SemanticType,
+        span: Span,
     },
     InvalidFnType {
         return_type: SemanticType,
         block_type: SemanticType,
+        span: Span,
     },
 }
+impl SemanticError {
+    //the source of the expression the error was found in
+    pub fn span(&self) -> Span {
+        match self {
+            SemanticError::UndeclaredVariable(_, span)
+            | SemanticError::UnrecognizedType(_, span)
+            | SemanticError::FunctionRedeclare(_, span)
+            | SemanticError::NestedFunction(_, span)
+            | SemanticError::AssignToImmutable(_, span)
+            | SemanticError::NotCallable(_, span)
+            | SemanticError::InvalidCondition(_, span)
+            | SemanticError::ProgramAnalysis(span)
+            | SemanticError::BreakOutsideLoop(span)
+            | SemanticError::ContinueOutsideLoop(span)
+            | SemanticError::ReturnOutsideFunction(span)
+            | SemanticError::InvalidAssignment { span, .. }
+            | SemanticError::InvalidArgCount { span, .. }
+            | SemanticError::InvalidArgType { span, .. }
+            | SemanticError::InvalidLiteral { span, .. }
+            | SemanticError::InvalidBinExpr { span, .. }
+            | SemanticError::InvalidOperator { span, .. }
+            | SemanticError::InvalidCast { span, .. }
+            | SemanticError::InvalidRange { span, .. }
+            | SemanticError::InvalidReturnType { span, .. }
+            | SemanticError::MismatchedBranches { span, .. }
+            | SemanticError::InvalidFnType { span, .. } => *span,
+        }
+    }
+}
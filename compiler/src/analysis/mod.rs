@@ -1,120 +1,766 @@
 pub mod errors;
-use parser::parsing::{Expression, Param};
+use parser::{
+    parsing::{Expression, ExpressionKind, LetDeclKind, Param},
+    tokenizer::{Operator, Span},
+};
 use std::collections::HashMap;
 
-use self::errors::SemanticError;
+use self::errors::{LitParseError, SemanticError};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SemanticType {
+    Int8,
+    Int16,
     Int32,
+    Int64,
+    Isize,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Usize,
     Float32,
+    Float64,
+    Bool,
+    //a unicode scalar value
+    Char,
+    Str,
     Void,
+    //type of expressions that never produce a value, such as break
+    Never,
     FnType {
         params: Vec<SemanticType>,
         rtype: Box<SemanticType>,
     },
 }
+impl SemanticType {
+    pub fn is_integer(&self) -> bool {
+        self.int_width().is_some()
+    }
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            SemanticType::Int8
+                | SemanticType::Int16
+                | SemanticType::Int32
+                | SemanticType::Int64
+                | SemanticType::Isize
+        )
+    }
+    pub fn is_float(&self) -> bool {
+        matches!(self, SemanticType::Float32 | SemanticType::Float64)
+    }
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+    //bit width of integer types, isize and usize are pointer sized which is always 64 for now
+    pub fn int_width(&self) -> Option<u32> {
+        Some(match self {
+            SemanticType::Int8 | SemanticType::Uint8 => 8,
+            SemanticType::Int16 | SemanticType::Uint16 => 16,
+            SemanticType::Int32 | SemanticType::Uint32 => 32,
+            SemanticType::Int64 | SemanticType::Uint64 => 64,
+            SemanticType::Isize | SemanticType::Usize => 64,
+            _ => return None,
+        })
+    }
+}
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub stype: SemanticType,
+    pub mutable: bool,
+}
 #[derive(Debug)]
 pub struct SemanticAnalayzer {
-    variables: HashMap<String, SemanticType>,
+    //innermost scope is the last one, the first holds the global declarations
+    scopes: Vec<HashMap<String, Variable>>,
+    loops: usize,
+    //return types of the functions being analyzed
+    functions: Vec<SemanticType>,
 }
 impl SemanticAnalayzer {
     pub fn new() -> Self {
         Self {
-            variables: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            loops: 0,
+            functions: Vec::new(),
         }
     }
-    pub fn get_type(s: Option<&str>) -> Result<SemanticType, SemanticError> {
+    pub fn get_type(s: Option<&str>, span: Span) -> Result<SemanticType, SemanticError> {
         let Some(s) = s else {
             return Ok(SemanticType::Void);
         };
         Ok(match s {
+            "int8" => SemanticType::Int8,
+            "int16" => SemanticType::Int16,
             "int32" => SemanticType::Int32,
+            "int64" => SemanticType::Int64,
+            "isize" => SemanticType::Isize,
+            "uint8" => SemanticType::Uint8,
+            "uint16" => SemanticType::Uint16,
+            "uint32" => SemanticType::Uint32,
+            "uint64" => SemanticType::Uint64,
+            "usize" => SemanticType::Usize,
             "f32" => SemanticType::Float32,
+            "f64" => SemanticType::Float64,
+            "bool" => SemanticType::Bool,
+            "char" => SemanticType::Char,
+            "str" => SemanticType::Str,
             "void" => SemanticType::Void,
-            _ => return Err(SemanticError::UnrecognizedType(s.to_string())),
+            _ => return Err(SemanticError::UnrecognizedType(s.to_string(), span)),
         })
     }
-    pub fn delete_var(&mut self, varname: &String) -> Option<SemanticType> {
-        self.variables.remove(varname)
+    pub fn enter_loop(&mut self) {
+        self.loops += 1;
+    }
+    pub fn exit_loop(&mut self) {
+        self.loops -= 1;
+    }
+    pub fn enter_function(&mut self, rtype: SemanticType) {
+        self.functions.push(rtype);
+    }
+    pub fn exit_function(&mut self) {
+        self.functions.pop();
+    }
+    pub fn return_type(&self) -> Option<&SemanticType> {
+        self.functions.last()
+    }
+    pub fn enter_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+    pub fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+    fn scope(&mut self) -> &mut HashMap<String, Variable> {
+        self.scopes.last_mut().unwrap()
+    }
+    fn lookup(&self, varname: &String, span: Span) -> Result<&Variable, SemanticError> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(varname))
+            .ok_or(SemanticError::UndeclaredVariable(varname.clone(), span))
+    }
+    pub fn declare_var(&mut self, varname: &String, stype: SemanticType) -> Option<SemanticType> {
+        self.bind_var(varname, stype, &LetDeclKind::Normal)
     }
     pub fn create_var(
         &mut self,
         varname: &String,
         expr: &Expression,
+        kind: &LetDeclKind,
     ) -> Result<(SemanticType, Option<SemanticType>), SemanticError> {
         let stype = self.analyze_expr(expr)?;
-        let old_type = self.variables.insert(varname.clone(), stype.clone());
+        let old_type = self.bind_var(varname, stype.clone(), kind);
         Ok((stype, old_type))
     }
-    pub fn analyze_var(&self, varname: &String) -> Result<&SemanticType, SemanticError> {
-        self.variables
-            .get(varname)
-            .ok_or(SemanticError::UndeclaredVariable(varname.clone()))
+    //declares a variable whose initializer was already analyzed
+    pub fn bind_var(
+        &mut self,
+        varname: &String,
+        stype: SemanticType,
+        kind: &LetDeclKind,
+    ) -> Option<SemanticType> {
+        //the wildcard binding is never declared, so it cannot be referred to
+        if varname == "_" {
+            return None;
+        }
+        let old_type = self.scope().insert(
+            varname.clone(),
+            Variable {
+                stype,
+                mutable: matches!(kind, LetDeclKind::Mutable),
+            },
+        );
+        old_type.map(|var| var.stype)
+    }
+    pub fn declare_params(
+        &mut self,
+        params: &[Param],
+        span: Span,
+    ) -> Result<Vec<SemanticType>, SemanticError> {
+        let mut parameters = Vec::with_capacity(params.len());
+        for param in params {
+            let ptype = Self::get_type(Some(&param.kind), span)?;
+            self.declare_var(&param.name, ptype.clone());
+            parameters.push(ptype);
+        }
+        Ok(parameters)
+    }
+    pub fn function_type(
+        params: &[Param],
+        rtype: Option<&str>,
+        span: Span,
+    ) -> Result<SemanticType, SemanticError> {
+        let mut parameters = Vec::with_capacity(params.len());
+        for param in params {
+            parameters.push(Self::get_type(Some(&param.kind), span)?);
+        }
+        Ok(SemanticType::FnType {
+            params: parameters,
+            rtype: Box::new(Self::get_type(rtype, span)?),
+        })
+    }
+    pub fn declare_function(
+        &mut self,
+        identifier: &String,
+        params: &[Param],
+        rtype: Option<&str>,
+        span: Span,
+    ) -> Result<SemanticType, SemanticError> {
+        let ftype = Self::function_type(params, rtype, span)?;
+        if let Some(_) = self.declare_var(identifier, ftype.clone()) {
+            return Err(SemanticError::FunctionRedeclare(identifier.clone(), span));
+        }
+        Ok(ftype)
+    }
+    //registers every function signature before any body is analyzed, so they can call each other
+    //regardless of the order they were declared
+    pub fn collect_declarations(&mut self, exprs: &[Expression]) -> Result<(), SemanticError> {
+        for expr in exprs {
+            if let ExpressionKind::FuncDecl {
+                identifier,
+                params,
+                rtype,
+                ..
+            } = &expr.kind
+            {
+                self.declare_function(identifier, params, rtype.as_deref(), expr.span)?;
+            }
+        }
+        Ok(())
+    }
+    //splits a literal such as 1.5f64 into its digits and type suffix
+    pub fn split_literal(lit: &str) -> (&str, Option<&str>) {
+        match lit.find(['f', 'i', 'u']) {
+            Some(i) => (&lit[..i], Some(&lit[i..])),
+            None => (lit, None),
+        }
+    }
+    fn suffix_type(suffix: &str, span: Span) -> Result<SemanticType, SemanticError> {
+        Ok(match suffix {
+            "i8" => SemanticType::Int8,
+            "i16" => SemanticType::Int16,
+            "i32" => SemanticType::Int32,
+            "i64" => SemanticType::Int64,
+            "isize" => SemanticType::Isize,
+            "u8" => SemanticType::Uint8,
+            "u16" => SemanticType::Uint16,
+            "u32" => SemanticType::Uint32,
+            "u64" => SemanticType::Uint64,
+            "usize" => SemanticType::Usize,
+            "f32" => SemanticType::Float32,
+            "f64" => SemanticType::Float64,
+            _ => return Err(SemanticError::UnrecognizedType(suffix.to_string(), span)),
+        })
+    }
+    //the literal under any number of negations, along with whether it ends up negative
+    fn literal(expr: &Expression) -> Option<(&ExpressionKind, bool)> {
+        match &expr.kind {
+            kind @ (ExpressionKind::IntLit(_) | ExpressionKind::FloatLit(_)) => Some((kind, false)),
+            ExpressionKind::Negative(expr) => {
+                Self::literal(expr).map(|(literal, negative)| (literal, !negative))
+            }
+            _ => None,
+        }
+    }
+    fn is_untyped_literal(expr: &Expression) -> bool {
+        match Self::literal(expr) {
+            Some((ExpressionKind::IntLit(lit) | ExpressionKind::FloatLit(lit), _)) => {
+                Self::split_literal(lit).1.is_none()
+            }
+            _ => false,
+        }
+    }
+    //literals without a suffix take the type expected by their context instead of the default one
+    pub fn literal_type(expr: &Expression, expected: &SemanticType) -> Option<SemanticType> {
+        if !Self::is_untyped_literal(expr) {
+            return None;
+        }
+        let negated = matches!(expr.kind, ExpressionKind::Negative(_));
+        match Self::literal(expr)? {
+            (ExpressionKind::IntLit(..), _)
+                if expected.is_signed() || (expected.is_integer() && !negated) =>
+            {
+                Some(expected.clone())
+            }
+            (ExpressionKind::FloatLit(..), _) if expected.is_float() => Some(expected.clone()),
+            _ => None,
+        }
+    }
+    fn check_literal(expr: &Expression, stype: &SemanticType) -> Result<(), SemanticError> {
+        let Some((ExpressionKind::IntLit(lit) | ExpressionKind::FloatLit(lit), negative)) =
+            Self::literal(expr)
+        else {
+            unreachable!();
+        };
+        //negations are part of the literal, so the error points at them too
+        let invalid = |error| SemanticError::InvalidLiteral {
+            error,
+            span: expr.span,
+        };
+        let (digits, _) = Self::split_literal(lit);
+        let fits = if let Some(width) = stype.int_width() {
+            let value = digits
+                .parse::<u128>()
+                .map_err(|e| invalid(LitParseError::Int(e)))?;
+            //negative signed values reach one further than the positive ones
+            let max = if stype.is_signed() {
+                (1 << (width - 1)) - 1 + negative as u128
+            } else {
+                (1 << width) - 1
+            };
+            value <= max
+        } else {
+            let value = digits
+                .parse::<f64>()
+                .map_err(|e| invalid(LitParseError::Float(e)))?;
+            value.is_finite() && (*stype != SemanticType::Float32 || (value as f32).is_finite())
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(invalid(LitParseError::OutOfRange(stype.clone())))
+        }
+    }
+    //literals on their own take the type of their suffix, or int32 and f32 when there is none
+    fn analyze_literal(expr: &Expression) -> Result<SemanticType, SemanticError> {
+        let (lit, default) = match Self::literal(expr) {
+            Some((ExpressionKind::IntLit(lit), _)) => (lit, SemanticType::Int32),
+            Some((ExpressionKind::FloatLit(lit), _)) => (lit, SemanticType::Float32),
+            _ => unreachable!(),
+        };
+        let stype = Self::split_literal(lit)
+            .1
+            .map_or(Ok(default), |suffix| Self::suffix_type(suffix, expr.span))?;
+        Self::check_literal(expr, &stype)?;
+        Ok(stype)
+    }
+    pub fn analyze_expr_expecting(
+        &mut self,
+        expr: &Expression,
+        expected: &SemanticType,
+    ) -> Result<SemanticType, SemanticError> {
+        match &expr.kind {
+            //the expected type reaches the value that blocks and ifs yield
+            ExpressionKind::Block(exprs) => self.analyze_block(exprs, Some(expected)),
+            ExpressionKind::If {
+                condition,
+                then_block,
+                else_block,
+            } => self.analyze_if(
+                condition,
+                then_block,
+                else_block.as_deref(),
+                expr.span,
+                Some(expected),
+            ),
+            _ => match Self::literal_type(expr, expected) {
+                Some(stype) => {
+                    Self::check_literal(expr, &stype)?;
+                    Ok(stype)
+                }
+                None => self.analyze_expr(expr),
+            },
+        }
+    }
+    fn analyze_tail(
+        &mut self,
+        expr: &Expression,
+        expected: Option<&SemanticType>,
+    ) -> Result<SemanticType, SemanticError> {
+        match expected {
+            Some(expected) => self.analyze_expr_expecting(expr, expected),
+            None => self.analyze_expr(expr),
+        }
+    }
+    pub fn analyze_var(
+        &self,
+        varname: &String,
+        span: Span,
+    ) -> Result<&SemanticType, SemanticError> {
+        self.lookup(varname, span).map(|var| &var.stype)
+    }
+    pub fn analyze_assign(
+        &mut self,
+        varname: &String,
+        expr: &Expression,
+        span: Span,
+    ) -> Result<SemanticType, SemanticError> {
+        let var_type = self.analyze_var(varname, span)?.clone();
+        let expr_type = self.analyze_expr_expecting(expr, &var_type)?;
+        let var = self.lookup(varname, span)?;
+        if !var.mutable {
+            return Err(SemanticError::AssignToImmutable(varname.clone(), span));
+        }
+        if var.stype != expr_type && expr_type != SemanticType::Never {
+            return Err(SemanticError::InvalidAssignment {
+                var_type: var.stype.clone(),
+                expr_type,
+                span,
+            });
+        }
+        Ok(SemanticType::Void)
+    }
+    pub fn analyze_call(
+        &mut self,
+        identifier: &String,
+        args: &[Expression],
+        span: Span,
+    ) -> Result<SemanticType, SemanticError> {
+        let SemanticType::FnType { params, rtype } = self.analyze_var(identifier, span)?.clone()
+        else {
+            return Err(SemanticError::NotCallable(identifier.clone(), span));
+        };
+        if params.len() != args.len() {
+            return Err(SemanticError::InvalidArgCount {
+                function: identifier.clone(),
+                expected: params.len(),
+                received: args.len(),
+                span,
+            });
+        }
+        for (param, arg) in params.into_iter().zip(args) {
+            let arg_type = self.analyze_expr_expecting(arg, &param)?;
+            if param != arg_type && arg_type != SemanticType::Never {
+                return Err(SemanticError::InvalidArgType {
+                    function: identifier.clone(),
+                    expected: param,
+                    received: arg_type,
+                    span: arg.span,
+                });
+            }
+        }
+        Ok(*rtype)
+    }
+    //an untyped literal on either side takes the type of the other operand
+    pub fn analyze_operands(
+        &mut self,
+        lhs: &Expression,
+        rhs: &Expression,
+    ) -> Result<(SemanticType, SemanticType), SemanticError> {
+        if Self::is_untyped_literal(lhs) {
+            let rhs = self.analyze_expr(rhs)?;
+            Ok((self.analyze_expr_expecting(lhs, &rhs)?, rhs))
+        } else {
+            let lhs = self.analyze_expr(lhs)?;
+            let rhs = self.analyze_expr_expecting(rhs, &lhs)?;
+            Ok((lhs, rhs))
+        }
     }
     pub fn analyze_binexpr(
         &mut self,
         lhs: &Expression,
         rhs: &Expression,
+        operator: Operator,
+        span: Span,
     ) -> Result<SemanticType, SemanticError> {
-        let lhs = self.analyze_expr(lhs)?;
-        let rhs = self.analyze_expr(rhs)?;
-        if lhs == rhs {
-            Ok(lhs)
-        } else {
-            Err(SemanticError::InvalidBinExpr {
+        let (lhs, rhs) = match self.analyze_operands(lhs, rhs)? {
+            (SemanticType::Never, SemanticType::Never) => return Ok(SemanticType::Never),
+            //a diverging operand never produces a value, so it fits the other one
+            (SemanticType::Never, stype) | (stype, SemanticType::Never) => (stype.clone(), stype),
+            operands => operands,
+        };
+        if lhs != rhs {
+            return Err(SemanticError::InvalidBinExpr {
                 lhs_type: lhs,
                 rhs_type: rhs,
+                span,
+            });
+        }
+        let valid = match operator {
+            Operator::EqEq | Operator::NotEq => {
+                lhs.is_numeric() || matches!(lhs, SemanticType::Bool | SemanticType::Char)
+            }
+            Operator::And | Operator::Or => lhs == SemanticType::Bool,
+            operator if operator.is_bitwise() => lhs.is_integer(),
+            operator if operator.is_comparison() => lhs.is_numeric() || lhs == SemanticType::Char,
+            _ => lhs.is_numeric(),
+        };
+        if !valid {
+            Err(SemanticError::InvalidOperator {
+                operator,
+                operand_type: lhs,
+                span,
             })
+        } else if operator.is_comparison() {
+            Ok(SemanticType::Bool)
+        } else {
+            Ok(lhs)
+        }
+    }
+    pub fn analyze_cast(
+        &mut self,
+        expr: &Expression,
+        to: &str,
+        span: Span,
+    ) -> Result<SemanticType, SemanticError> {
+        let to = Self::get_type(Some(to), span)?;
+        let from = self.analyze_cast_source(expr, &to)?;
+        let valid = match (&from, &to) {
+            (from, to) if from == to => true,
+            //any byte is a valid char, wider integers could fall outside the unicode scalar values
+            (SemanticType::Uint8, SemanticType::Char) => true,
+            //bools and chars can be turned into integers, but there is no cast back
+            (SemanticType::Bool | SemanticType::Char, to) => to.is_integer(),
+            (from, to) => from.is_numeric() && to.is_numeric(),
+        };
+        if valid {
+            Ok(to)
+        } else {
+            Err(SemanticError::InvalidCast { from, to, span })
+        }
+    }
+    //an untyped literal cast to char is taken as a byte, so 65 as char is valid
+    pub fn analyze_cast_source(
+        &mut self,
+        expr: &Expression,
+        to: &SemanticType,
+    ) -> Result<SemanticType, SemanticError> {
+        match to {
+            SemanticType::Char => self.analyze_expr_expecting(expr, &SemanticType::Uint8),
+            _ => self.analyze_expr(expr),
+        }
+    }
+    pub fn analyze_if(
+        &mut self,
+        condition: &Expression,
+        then_block: &Expression,
+        else_block: Option<&Expression>,
+        span: Span,
+        expected: Option<&SemanticType>,
+    ) -> Result<SemanticType, SemanticError> {
+        let condition_type = self.analyze_expr(condition)?;
+        if condition_type != SemanticType::Bool {
+            return Err(SemanticError::InvalidCondition(
+                condition_type,
+                condition.span,
+            ));
+        }
+        let then_type = self.analyze_tail(then_block, expected)?;
+        let Some(else_block) = else_block else {
+            return Ok(SemanticType::Void);
+        };
+        let else_type = self.analyze_tail(else_block, expected)?;
+        match (then_type, else_type) {
+            (SemanticType::Never, stype) | (stype, SemanticType::Never) => Ok(stype),
+            (then_type, else_type) if then_type == else_type => Ok(then_type),
+            (then_type, else_type) => Err(SemanticError::MismatchedBranches {
+                then_type,
+                else_type,
+                span,
+            }),
+        }
+    }
+    pub fn analyze_while(
+        &mut self,
+        condition: &Expression,
+        block: &Expression,
+    ) -> Result<SemanticType, SemanticError> {
+        let condition_type = self.analyze_expr(condition)?;
+        if condition_type != SemanticType::Bool {
+            return Err(SemanticError::InvalidCondition(
+                condition_type,
+                condition.span,
+            ));
+        }
+        self.enter_loop();
+        let block = self.analyze_expr(block);
+        self.exit_loop();
+        block?;
+        Ok(SemanticType::Void)
+    }
+    pub fn analyze_for(
+        &mut self,
+        varname: &String,
+        start: &Expression,
+        end: &Expression,
+        block: &Expression,
+    ) -> Result<SemanticType, SemanticError> {
+        //an untyped literal bound takes the type of the other one, like binary operands do
+        let (start_type, end_type) = self.analyze_operands(start, end)?;
+        if start_type != end_type || !start_type.is_integer() {
+            return Err(SemanticError::InvalidRange {
+                start_type,
+                end_type,
+                span: start.span.to(end.span),
+            });
+        }
+        self.enter_scope();
+        self.declare_var(varname, start_type);
+        self.enter_loop();
+        let block = self.analyze_expr(block);
+        self.exit_loop();
+        self.exit_scope();
+        block?;
+        Ok(SemanticType::Void)
+    }
+    pub fn analyze_return(
+        &mut self,
+        expr: Option<&Expression>,
+        span: Span,
+    ) -> Result<SemanticType, SemanticError> {
+        let Some(expected) = self.return_type().cloned() else {
+            return Err(SemanticError::ReturnOutsideFunction(span));
+        };
+        let received = if let Some(expr) = expr {
+            self.analyze_expr_expecting(expr, &expected)?
+        } else {
+            SemanticType::Void
+        };
+        if received == expected || received == SemanticType::Never {
+            Ok(SemanticType::Never)
+        } else {
+            Err(SemanticError::InvalidReturnType {
+                expected,
+                received,
+                span,
+            })
+        }
+    }
+    fn analyze_block(
+        &mut self,
+        exprs: &[Expression],
+        expected: Option<&SemanticType>,
+    ) -> Result<SemanticType, SemanticError> {
+        self.enter_scope();
+        let stype = self.analyze_scoped_block(exprs, expected);
+        self.exit_scope();
+        stype
+    }
+    fn analyze_scoped_block(
+        &mut self,
+        exprs: &[Expression],
+        expected: Option<&SemanticType>,
+    ) -> Result<SemanticType, SemanticError> {
+        if let Some((last, rest)) = exprs.split_last() {
+            let mut diverges = false;
+            for expr in rest {
+                diverges |= self.analyze_expr(expr)? == SemanticType::Never;
+            }
+            let stype = self.analyze_tail(last, expected)?;
+            //the block never reaches its last expression after a statement that jumps away
+            Ok(if diverges { SemanticType::Never } else { stype })
+        } else {
+            Ok(SemanticType::Void)
         }
     }
     pub fn analyze_expr(&mut self, expr: &Expression) -> Result<SemanticType, SemanticError> {
-        Ok(match expr {
-            Expression::IntLit(_) => SemanticType::Int32,
-            Expression::FloatLit(_) => SemanticType::Float32,
-            Expression::LetDecl { varname, expr, .. } => self.create_var(varname, &**expr)?.0,
-            Expression::Identifier(s) => self.analyze_var(s)?.clone(),
-            Expression::Program(_) => return Err(SemanticError::ProgramAnalysis),
-            Expression::BinExpr { lhs, rhs, .. } => self.analyze_binexpr(&**lhs, &**rhs)?,
-            Expression::Negative(expr) => self.analyze_expr(&**expr)?,
-            Expression::Block(exprs) => {
-                if let Some((last, rest)) = exprs.split_last() {
-                    for expr in rest {
-                        self.analyze_expr(expr)?;
-                    }
-                    self.analyze_expr(last)?
-                } else {
-                    SemanticType::Int32
+        let span = expr.span;
+        Ok(match &expr.kind {
+            ExpressionKind::IntLit(..) | ExpressionKind::FloatLit(..) => {
+                Self::analyze_literal(expr)?
+            }
+            ExpressionKind::BoolLit(_) => SemanticType::Bool,
+            ExpressionKind::StrLit(_) => SemanticType::Str,
+            ExpressionKind::CharLit(_) => SemanticType::Char,
+            ExpressionKind::LetDecl {
+                kind,
+                varname,
+                expr,
+                ..
+            } => self.create_var(varname, &**expr, kind)?.0,
+            ExpressionKind::Assign { varname, expr } => self.analyze_assign(varname, expr, span)?,
+            ExpressionKind::Identifier(s) => self.analyze_var(s, span)?.clone(),
+            ExpressionKind::Program(_) => return Err(SemanticError::ProgramAnalysis(span)),
+            ExpressionKind::BinExpr { lhs, rhs, op } => {
+                self.analyze_binexpr(&**lhs, &**rhs, *op, span)?
+            }
+            ExpressionKind::If {
+                condition,
+                then_block,
+                else_block,
+            } => self.analyze_if(condition, then_block, else_block.as_deref(), span, None)?,
+            ExpressionKind::While { condition, block } => self.analyze_while(condition, block)?,
+            ExpressionKind::For {
+                varname,
+                start,
+                end,
+                block,
+                ..
+            } => self.analyze_for(varname, start, end, block)?,
+            ExpressionKind::Break if self.loops == 0 => {
+                return Err(SemanticError::BreakOutsideLoop(span))
+            }
+            ExpressionKind::Continue if self.loops == 0 => {
+                return Err(SemanticError::ContinueOutsideLoop(span))
+            }
+            ExpressionKind::Break | ExpressionKind::Continue => SemanticType::Never,
+            ExpressionKind::Return(expr) => self.analyze_return(expr.as_deref(), span)?,
+            ExpressionKind::Call { identifier, args } => {
+                self.analyze_call(identifier, args, span)?
+            }
+            ExpressionKind::Cast { expr, to } => self.analyze_cast(expr, to, span)?,
+            ExpressionKind::Negative(inner) => {
+                let stype = match Self::literal(expr) {
+                    //checked as a whole so that the negation is taken into account
+                    Some(_) => Self::analyze_literal(expr)?,
+                    None => self.analyze_expr(&**inner)?,
+                };
+                if !stype.is_numeric() || (stype.is_integer() && !stype.is_signed()) {
+                    return Err(SemanticError::InvalidOperator {
+                        operator: Operator::Minus,
+                        operand_type: stype,
+                        span,
+                    });
+                }
+                stype
+            }
+            ExpressionKind::Not(expr) => {
+                let stype = self.analyze_expr(&**expr)?;
+                if stype != SemanticType::Bool {
+                    return Err(SemanticError::InvalidOperator {
+                        operator: Operator::Not,
+                        operand_type: stype,
+                        span,
+                    });
                 }
+                stype
             }
-            Expression::FuncDecl {
+            ExpressionKind::BitNot(expr) => {
+                let stype = self.analyze_expr(&**expr)?;
+                if !stype.is_integer() {
+                    return Err(SemanticError::InvalidOperator {
+                        operator: Operator::Tilde,
+                        operand_type: stype,
+                        span,
+                    });
+                }
+                stype
+            }
+            ExpressionKind::Block(exprs) => self.analyze_block(exprs, None)?,
+            ExpressionKind::FuncDecl {
                 identifier,
                 params,
                 rtype,
                 block,
+                ..
             } => {
-                let rtype = Self::get_type(rtype.as_deref())?;
-                let block_type = self.analyze_expr(&**block)?;
-                if block_type == rtype {
-                    let params = {
-                        let mut parameters = Vec::with_capacity(params.len());
-                        for param in params {
-                            parameters.push(Self::get_type(Some(&param.kind))?);
-                        }
-                        parameters
-                    };
-                    let ftype = SemanticType::FnType {
-                        params,
-                        rtype: Box::new(rtype),
-                    };
-                    if let Some(_) = self.variables.insert(identifier.to_string(), ftype.clone()) {
-                        return Err(SemanticError::FunctionRedeclare(identifier.clone()));
-                    };
+                //a nested function could not reach the locals of the one around it
+                if self.scopes.len() > 1 {
+                    return Err(SemanticError::NestedFunction(identifier.clone(), span));
+                }
+                let ftype = match self.scope().get(identifier) {
+                    //already declared by collect_declarations
+                    Some(var) => var.stype.clone(),
+                    None => self.declare_function(identifier, params, rtype.as_deref(), span)?,
+                };
+                let rtype = Self::get_type(rtype.as_deref(), span)?;
+                self.enter_scope();
+                self.enter_function(rtype.clone());
+                let block_type = self
+                    .declare_params(params, span)
+                    .and_then(|_| self.analyze_expr_expecting(&**block, &rtype));
+                self.exit_function();
+                self.exit_scope();
+                let block_type = block_type?;
+                if block_type == rtype || block_type == SemanticType::Never {
                     ftype
                 } else {
                     return Err(SemanticError::InvalidFnType {
                         return_type: rtype,
                         block_type,
+                        span,
                     });
                 }
             }
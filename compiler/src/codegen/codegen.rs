@@ -3,16 +3,18 @@ use std::{collections::HashMap, path::Path};
 use super::errors::CompilationError;
 use crate::analysis::{errors::SemanticError, SemanticAnalayzer, SemanticType};
 use inkwell::{
+    basic_block::BasicBlock,
     builder::Builder,
     context::Context,
+    module::Linkage,
     module::Module,
-    types::{BasicType, BasicTypeEnum, FloatType, FunctionType, IntType, VoidType},
+    types::{BasicType, BasicTypeEnum, FloatType, FunctionType, IntType, StructType, VoidType},
     values::{BasicValue, BasicValueEnum, FunctionValue, PointerValue},
-    AddressSpace,
+    AddressSpace, FloatPredicate, IntPredicate,
 };
 use parser::{
-    parsing::{Expression, LetDeclKind, Param},
-    tokenizer::Operator,
+    parsing::{Expression, ExpressionKind, LetDeclKind, Param},
+    tokenizer::{Operator, Span},
 };
 
 #[derive(Debug)]
@@ -21,7 +23,15 @@ pub struct CodeGenerator<'a> {
     module: Module<'a>,
     context: &'a Context,
     analyzer: SemanticAnalayzer,
-    variables: HashMap<String, PointerValue<'a>>,
+    //kept in sync with the scopes of the analyzer
+    variables: Vec<HashMap<String, PointerValue<'a>>>,
+    loops: Vec<LoopBlocks<'a>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LoopBlocks<'a> {
+    continue_block: BasicBlock<'a>,
+    break_block: BasicBlock<'a>,
 }
 
 pub enum CodeGenType<'a> {
@@ -41,7 +51,8 @@ impl<'a> CodeGenerator<'a> {
             builder,
             module,
             analyzer: SemanticAnalayzer::new(),
-            variables: HashMap::new(),
+            variables: vec![HashMap::new()],
+            loops: Vec::new(),
         }
     }
     pub fn compile_source(
@@ -65,27 +76,79 @@ impl<'a> CodeGenerator<'a> {
     pub fn i32(&self) -> IntType<'a> {
         self.context.i32_type()
     }
+    pub fn int(&self, bits: u32) -> IntType<'a> {
+        self.context.custom_width_int_type(bits)
+    }
     pub fn f32(&self) -> FloatType<'a> {
         self.context.f32_type()
     }
+    pub fn f64(&self) -> FloatType<'a> {
+        self.context.f64_type()
+    }
+    pub fn bool(&self) -> IntType<'a> {
+        self.context.bool_type()
+    }
+    //strings are a pointer to their bytes along with their length
+    pub fn str(&self) -> StructType<'a> {
+        self.context.struct_type(
+            &[
+                self.context
+                    .i8_type()
+                    .ptr_type(AddressSpace::default())
+                    .into(),
+                self.int(64).into(),
+            ],
+            false,
+        )
+    }
     pub fn void(&self) -> VoidType<'a> {
         self.context.void_type()
     }
-    fn load(&self, vname: &String) -> Result<BasicValueEnum<'a>, CompilationError> {
-        let varptr = self
-            .variables
-            .get(vname)
-            .ok_or(CompilationError::UndeclaredVariable(vname.clone()))?;
+    fn enter_scope(&mut self) {
+        self.variables.push(HashMap::new());
+        self.analyzer.enter_scope();
+    }
+    fn exit_scope(&mut self) {
+        self.variables.pop();
+        self.analyzer.exit_scope();
+    }
+    fn declare(&mut self, vname: String, varptr: PointerValue<'a>) {
+        self.variables.last_mut().unwrap().insert(vname, varptr);
+    }
+    fn lookup(&self, vname: &String, span: Span) -> Result<PointerValue<'a>, CompilationError> {
+        self.variables
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(vname).copied())
+            .ok_or(CompilationError::UndeclaredVariable(vname.clone(), span))
+    }
+    fn load(&self, vname: &String, span: Span) -> Result<BasicValueEnum<'a>, CompilationError> {
+        let varptr = self.lookup(vname, span)?;
         Ok(self
             .builder
-            .build_load(*varptr, &format!("load-{vname}"))
+            .build_load(varptr, &format!("load-{vname}"))
             .unwrap())
     }
     fn type_from_stype(&self, stype: &SemanticType) -> Option<CodeGenType<'a>> {
         Some(match stype {
-            SemanticType::Int32 => CodeGenType::Primitive(self.i32().as_basic_type_enum()),
+            SemanticType::Int8
+            | SemanticType::Int16
+            | SemanticType::Int32
+            | SemanticType::Int64
+            | SemanticType::Isize
+            | SemanticType::Uint8
+            | SemanticType::Uint16
+            | SemanticType::Uint32
+            | SemanticType::Uint64
+            | SemanticType::Usize => {
+                CodeGenType::Primitive(self.int(stype.int_width().unwrap()).as_basic_type_enum())
+            }
             SemanticType::Float32 => CodeGenType::Primitive(self.f32().as_basic_type_enum()),
-            SemanticType::Void => return None,
+            SemanticType::Float64 => CodeGenType::Primitive(self.f64().as_basic_type_enum()),
+            SemanticType::Bool => CodeGenType::Primitive(self.bool().as_basic_type_enum()),
+            SemanticType::Char => CodeGenType::Primitive(self.i32().as_basic_type_enum()),
+            SemanticType::Str => CodeGenType::Primitive(self.str().as_basic_type_enum()),
+            SemanticType::Void | SemanticType::Never => return None,
             SemanticType::FnType { params, rtype } => {
                 let params = {
                     let mut param_types = Vec::with_capacity(params.len());
@@ -118,40 +181,38 @@ impl<'a> CodeGenerator<'a> {
             }
         })
     }
-    fn type_based_on_semantics(
-        &mut self,
-        expr: &Expression,
-    ) -> Result<Option<CodeGenType<'a>>, CompilationError> {
-        let stype = self
-            .analyzer
-            .analyze_expr(expr)
-            .map_err(CompilationError::TypeError)?;
-        Ok(self.type_from_stype(&stype))
+    fn basic_type_from_stype(&self, stype: &SemanticType) -> Option<BasicTypeEnum<'a>> {
+        Some(match self.type_from_stype(stype)? {
+            CodeGenType::Fn(f) => f.ptr_type(AddressSpace::default()).as_basic_type_enum(),
+            CodeGenType::Primitive(basic) => basic,
+        })
     }
     fn compile_ast(
         &mut self,
         expr: Expression,
     ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
-        Ok(match expr {
-            Expression::IntLit(s) => Some(
-                self.i32()
-                    .const_int(s.parse::<u64>().unwrap(), false)
-                    .as_basic_value_enum(),
-            ),
-            Expression::FloatLit(s) => Some(
-                self.f32()
-                    .const_float(s.parse::<f64>().unwrap())
-                    .as_basic_value_enum(),
-            ),
-            Expression::LetDecl {
+        let span = expr.span;
+        Ok(match expr.kind {
+            ExpressionKind::IntLit(..) | ExpressionKind::FloatLit(..) => {
+                let stype = self.analyzer.analyze_expr(&expr)?;
+                Some(self.compile_literal(&expr, &stype))
+            }
+            ExpressionKind::LetDecl {
                 kind,
                 varname,
                 expr,
-            } => Some(
-                self.compile_vardecl(kind, &varname, *expr)?
-                    .as_basic_value_enum(),
-            ),
-            Expression::Program(mut exprs) => {
+                ..
+            } => self
+                .compile_vardecl(kind, &varname, *expr)?
+                .map(|alloc| alloc.as_basic_value_enum()),
+            ExpressionKind::Program(mut exprs) => {
+                self.analyzer.collect_declarations(&exprs)?;
+                for expr in &exprs {
+                    if let ExpressionKind::FuncDecl { identifier, .. } = &expr.kind {
+                        let stype = self.analyzer.analyze_var(identifier, expr.span)?.clone();
+                        self.declare_function(identifier.clone(), &stype, expr.span)?;
+                    }
+                }
                 let last_expr = exprs.pop();
                 for expr in exprs {
                     self.compile_ast(expr)?;
@@ -162,27 +223,125 @@ impl<'a> CodeGenerator<'a> {
                     Ok(None)
                 };
             }
-            Expression::Identifier(s) => Some(self.load(&s)?.as_basic_value_enum()),
-            Expression::BinExpr { lhs, rhs, op } => {
-                let stype = self
-                    .analyzer
-                    .analyze_binexpr(&lhs, &rhs)
-                    .map_err(|e| CompilationError::TypeError(e))?;
-                Some(self.compile_binexpr(lhs, rhs, op, stype)?)
-            }
-            Expression::Negative(expr) => self.compile_negative(*expr)?,
-            Expression::Block(exprs) => self.compile_block(exprs)?,
-            Expression::FuncDecl {
+            ExpressionKind::Identifier(s) => Some(self.load(&s, span)?.as_basic_value_enum()),
+            ExpressionKind::StrLit(s) => Some(self.compile_str(&s)),
+            ExpressionKind::CharLit(c) => {
+                Some(self.i32().const_int(c as u64, false).as_basic_value_enum())
+            }
+            ExpressionKind::BoolLit(b) => {
+                Some(self.bool().const_int(b as u64, false).as_basic_value_enum())
+            }
+            ExpressionKind::Assign {
+                ref varname,
+                expr: ref value,
+            } => {
+                self.analyzer.analyze_expr(&expr)?;
+                let var_type = self.analyzer.analyze_var(varname, span)?.clone();
+                let varptr = self.lookup(varname, span)?;
+                if let Some(value) = self.compile_expecting(*value.clone(), &var_type)? {
+                    self.builder.build_store(varptr, value).unwrap();
+                }
+                None
+            }
+            ExpressionKind::BinExpr { lhs, rhs, op } if op.is_logical() => {
+                self.analyzer.analyze_binexpr(&lhs, &rhs, op, span)?;
+                self.compile_logical(*lhs, *rhs, op)?
+            }
+            ExpressionKind::BinExpr { lhs, rhs, op } => {
+                self.analyzer.analyze_binexpr(&lhs, &rhs, op, span)?;
+                let (stype, _) = self.analyzer.analyze_operands(&lhs, &rhs)?;
+                self.compile_binexpr(lhs, rhs, op, stype)?
+            }
+            ExpressionKind::If {
+                ref condition,
+                ref then_block,
+                ref else_block,
+            } => {
+                let stype = self.analyzer.analyze_expr(&expr)?;
+                self.compile_if(
+                    *condition.clone(),
+                    *then_block.clone(),
+                    else_block.clone().map(|e| *e),
+                    stype,
+                    None,
+                )?
+            }
+            ExpressionKind::While {
+                ref condition,
+                ref block,
+            } => {
+                self.analyzer.analyze_expr(&expr)?;
+                self.compile_while(*condition.clone(), *block.clone())?
+            }
+            ExpressionKind::For {
+                ref varname,
+                ref start,
+                ref end,
+                inclusive,
+                ref block,
+            } => {
+                self.analyzer.analyze_expr(&expr)?;
+                self.compile_for(
+                    varname,
+                    *start.clone(),
+                    *end.clone(),
+                    inclusive,
+                    *block.clone(),
+                )?
+            }
+            ExpressionKind::Break | ExpressionKind::Continue => {
+                self.compile_loop_jump(&expr)?;
+                None
+            }
+            ExpressionKind::Return(ref value) => {
+                self.analyzer.analyze_expr(&expr)?;
+                let expected = self.analyzer.return_type().cloned().unwrap();
+                let value = match value {
+                    Some(value) => self.compile_expecting(*value.clone(), &expected)?,
+                    None => None,
+                };
+                if !self.is_terminated() {
+                    self.builder
+                        .build_return(value.as_ref().map(|v| v as &dyn BasicValue))
+                        .unwrap();
+                }
+                None
+            }
+            ExpressionKind::Call { identifier, args } => {
+                self.analyzer.analyze_call(&identifier, &args, span)?;
+                self.compile_call(identifier, args, span)?
+            }
+            ExpressionKind::Cast {
+                expr: ref inner, ..
+            } => {
+                let to = self.analyzer.analyze_expr(&expr)?;
+                let from = self.analyzer.analyze_cast_source(inner, &to)?;
+                Some(self.compile_cast(*inner.clone(), &from, &to)?)
+            }
+            ExpressionKind::Negative(ref inner) => {
+                let stype = self.analyzer.analyze_expr(&expr)?;
+                self.compile_negative(*inner.clone(), &stype)?
+            }
+            ExpressionKind::Not(ref inner) | ExpressionKind::BitNot(ref inner) => {
+                self.analyzer.analyze_expr(&expr)?;
+                let value = self.compile_ast(*inner.clone())?.unwrap().into_int_value();
+                Some(
+                    self.builder
+                        .build_not(value, "not")
+                        .unwrap()
+                        .as_basic_value_enum(),
+                )
+            }
+            ExpressionKind::Block(exprs) => self.compile_block(exprs, None)?,
+            ExpressionKind::FuncDecl {
                 ref identifier,
+                ref params,
                 ref block,
                 ..
             } => {
-                let stype = self
-                    .analyzer
-                    .analyze_expr(&expr)
-                    .map_err(CompilationError::TypeError)?;
+                let stype = self.analyzer.analyze_expr(&expr)?;
                 Some(
-                    self.compile_func_decl(identifier.clone(), block.clone(), stype)?
+                    self.compile_func_decl(identifier.clone(), params, block.clone(), stype, span)?
                         .as_global_value()
                         .as_basic_value_enum(),
                 )
@@ -192,70 +351,510 @@ impl<'a> CodeGenerator<'a> {
     fn compile_func_decl(
         &mut self,
         identifier: String,
+        params: &[Param],
         block: Box<Expression>,
         stype: SemanticType,
+        span: Span,
     ) -> Result<FunctionValue<'a>, CompilationError> {
-        let ftype = {
-            self.analyzer
-                .create_var(&identifier, &*block)
-                .map_err(CompilationError::TypeError)?;
+        let f = match self.module.get_function(&identifier) {
+            Some(f) if f.count_basic_blocks() == 0 => f,
+            Some(_) => return Err(CompilationError::InvalidRedeclare(identifier, span)),
+            None => self.declare_function(identifier, &stype, span)?,
+        };
+        let entry = self.context.append_basic_block(f, "entry");
+        self.builder.position_at_end(entry);
 
-            let CodeGenType::Fn(func) = self.type_from_stype(&stype).unwrap() else {
+        let SemanticType::FnType { rtype, .. } = stype else {
+            unreachable!();
+        };
+        self.enter_scope();
+        self.analyzer.enter_function(*rtype.clone());
+        let value = self.compile_func_body(f, params, *block, &rtype, span);
+        self.analyzer.exit_function();
+        self.exit_scope();
+        let value = value?;
+        if !self.is_terminated() {
+            //reaching the end returns the value of the last expression
+            self.builder
+                .build_return(value.as_ref().map(|v| v as &dyn BasicValue))
+                .unwrap();
+        }
+        Ok(f)
+    }
+    fn declare_function(
+        &mut self,
+        identifier: String,
+        stype: &SemanticType,
+        span: Span,
+    ) -> Result<FunctionValue<'a>, CompilationError> {
+        let ftype = {
+            let CodeGenType::Fn(func) = self.type_from_stype(stype).unwrap() else {
                 //i know that it will be a function type
                 unreachable!();
             };
             func
         };
+        if self.variables.last().unwrap().contains_key(&identifier) {
+            return Err(CompilationError::InvalidRedeclare(identifier, span));
+        }
         let f = self.module.add_function(&identifier, ftype, None);
-        let entry = self.context.append_basic_block(f, "entry");
-        self.builder.position_at_end(entry);
+        self.declare(identifier, f.as_global_value().as_pointer_value());
+        Ok(f)
+    }
+    fn compile_func_body(
+        &mut self,
+        f: FunctionValue<'a>,
+        params: &[Param],
+        block: Expression,
+        rtype: &SemanticType,
+        span: Span,
+    ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
+        //parameter errors point at the whole declaration, as in the analysis pass
+        self.analyzer.declare_params(params, span)?;
+        for (i, param) in params.iter().enumerate() {
+            let value = f.get_nth_param(i as u32).unwrap();
+            value.set_name(&param.name);
+            let alloc = self
+                .builder
+                .build_alloca(value.get_type(), &param.name)
+                .unwrap();
+            self.builder.build_store(alloc, value).unwrap();
+            self.declare(param.name.clone(), alloc);
+        }
+        self.compile_expecting(block, rtype)
+    }
+    fn current_function(&self) -> FunctionValue<'a> {
+        self.builder
+            .get_insert_block()
+            .unwrap()
+            .get_parent()
+            .unwrap()
+    }
+    fn compile_logical(
+        &mut self,
+        lhs: Expression,
+        rhs: Expression,
+        operator: Operator,
+    ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
+        let Some(lhs) = self.compile_ast(lhs)? else {
+            return Ok(None);
+        };
+        let lhs = lhs.into_int_value();
+        let lhs_end = self.builder.get_insert_block().unwrap();
+        let function = self.current_function();
+        let rhs_bb = self.context.append_basic_block(function, "logicrhs");
+        let merge_bb = self.context.append_basic_block(function, "logiccont");
+        //the result is already known when && finds false or || finds true
+        let short_circuit = match operator {
+            Operator::And => {
+                self.builder
+                    .build_conditional_branch(lhs, rhs_bb, merge_bb)
+                    .unwrap();
+                self.bool().const_zero()
+            }
+            _ => {
+                self.builder
+                    .build_conditional_branch(lhs, merge_bb, rhs_bb)
+                    .unwrap();
+                self.bool().const_all_ones()
+            }
+        };
+
+        self.builder.position_at_end(rhs_bb);
+        //a right side that jumps away only leaves the short circuit to reach the merge
+        let rhs = self.compile_branch(rhs, merge_bb, None)?;
 
-        if self.variables.contains_key(&identifier) {
-            return Err(CompilationError::InvalidRedeclare(identifier));
+        self.builder.position_at_end(merge_bb);
+        let phi = self.builder.build_phi(self.bool(), "logicvalue").unwrap();
+        phi.add_incoming(&[(&short_circuit, lhs_end)]);
+        if let Some((Some(rhs), rhs_end)) = rhs {
+            phi.add_incoming(&[(&rhs, rhs_end)]);
+        }
+        Ok(Some(phi.as_basic_value()))
+    }
+    fn compile_if(
+        &mut self,
+        condition: Expression,
+        then_block: Expression,
+        else_block: Option<Expression>,
+        stype: SemanticType,
+        expected: Option<&SemanticType>,
+    ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
+        let condition = self.compile_ast(condition)?.unwrap().into_int_value();
+        let function = self.current_function();
+        let then_bb = self.context.append_basic_block(function, "then");
+        let else_bb = self.context.append_basic_block(function, "else");
+        let merge_bb = self.context.append_basic_block(function, "ifcont");
+        self.builder
+            .build_conditional_branch(condition, then_bb, else_bb)
+            .unwrap();
+
+        self.builder.position_at_end(then_bb);
+        let then_branch = self.compile_branch(then_block, merge_bb, expected)?;
+        self.builder.position_at_end(else_bb);
+        let else_branch = if let Some(else_block) = else_block {
+            self.compile_branch(else_block, merge_bb, expected)?
         } else {
-            self.variables
-                .insert(identifier, f.as_global_value().as_pointer_value());
+            self.builder.build_unconditional_branch(merge_bb).unwrap();
+            Some((None, else_bb))
         };
-        match *block {
-            Expression::Block(mut exprs) => {
-                let last = exprs.pop().unwrap();
-                for expr in exprs {
-                    self.compile_ast(expr)?;
-                }
-                if let Some(expr) = self.compile_ast(last)? {
-                    self.builder.build_return(Some(&expr)).unwrap();
-                }
-            }
-            expr => {
-                if let Some(expr) = self.compile_ast(expr)? {
-                    self.builder.build_return(Some(&expr)).unwrap();
+
+        self.builder.position_at_end(merge_bb);
+        if then_branch.is_none() && else_branch.is_none() {
+            //both branches jumped away, nothing can reach here
+            self.builder.build_unreachable().unwrap();
+            return Ok(None);
+        }
+        let incoming: Vec<(BasicValueEnum<'a>, BasicBlock<'a>)> = [then_branch, else_branch]
+            .into_iter()
+            .flatten()
+            .filter_map(|(value, block)| Some((value?, block)))
+            .collect();
+        Ok(match self.basic_type_from_stype(&stype) {
+            Some(btype) if !incoming.is_empty() => {
+                let phi = self.builder.build_phi(btype, "ifvalue").unwrap();
+                for (value, block) in incoming {
+                    phi.add_incoming(&[(&value, block)]);
                 }
+                Some(phi.as_basic_value())
             }
+            _ => None,
+        })
+    }
+    //compiles a branch that continues at `target`, returning its value and the block it ends in,
+    //or None when it already left through a terminator like break
+    fn compile_branch(
+        &mut self,
+        expr: Expression,
+        target: BasicBlock<'a>,
+        expected: Option<&SemanticType>,
+    ) -> Result<Option<(Option<BasicValueEnum<'a>>, BasicBlock<'a>)>, CompilationError> {
+        let value = self.compile_tail(expr, expected)?;
+        if self.is_terminated() {
+            return Ok(None);
+        }
+        let end = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(target).unwrap();
+        Ok(Some((value, end)))
+    }
+    fn compile_while(
+        &mut self,
+        condition: Expression,
+        block: Expression,
+    ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
+        let function = self.current_function();
+        let cond_bb = self.context.append_basic_block(function, "loopcond");
+        let body_bb = self.context.append_basic_block(function, "loopbody");
+        let exit_bb = self.context.append_basic_block(function, "loopexit");
+        self.builder.build_unconditional_branch(cond_bb).unwrap();
+
+        self.builder.position_at_end(cond_bb);
+        let condition = self.compile_ast(condition)?.unwrap().into_int_value();
+        self.builder
+            .build_conditional_branch(condition, body_bb, exit_bb)
+            .unwrap();
+
+        self.builder.position_at_end(body_bb);
+        self.compile_loop_body(block, cond_bb, exit_bb)?;
+        self.builder.position_at_end(exit_bb);
+        Ok(None)
+    }
+    fn compile_for(
+        &mut self,
+        varname: &String,
+        start: Expression,
+        end: Expression,
+        inclusive: bool,
+        block: Expression,
+    ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
+        let (stype, _) = self.analyzer.analyze_operands(&start, &end)?;
+        let start = self
+            .compile_expecting(start, &stype)?
+            .unwrap()
+            .into_int_value();
+        let end = self
+            .compile_expecting(end, &stype)?
+            .unwrap()
+            .into_int_value();
+        let counter = self.build_entry_alloca(start.get_type().as_basic_type_enum(), varname);
+        self.builder.build_store(counter, start).unwrap();
+
+        let function = self.current_function();
+        let cond_bb = self.context.append_basic_block(function, "forcond");
+        let body_bb = self.context.append_basic_block(function, "forbody");
+        let latch_bb = self.context.append_basic_block(function, "forlatch");
+        let exit_bb = self.context.append_basic_block(function, "forexit");
+        self.builder.build_unconditional_branch(cond_bb).unwrap();
+
+        self.builder.position_at_end(cond_bb);
+        let current = self
+            .builder
+            .build_load(counter, varname)
+            .unwrap()
+            .into_int_value();
+        let predicate = Self::int_predicate(
+            if inclusive {
+                Operator::LessEq
+            } else {
+                Operator::Less
+            },
+            stype.is_signed(),
+        )
+        .unwrap();
+        let condition = self
+            .builder
+            .build_int_compare(predicate, current, end, "forcheck")
+            .unwrap();
+        self.builder
+            .build_conditional_branch(condition, body_bb, exit_bb)
+            .unwrap();
+
+        self.builder.position_at_end(body_bb);
+        self.enter_scope();
+        self.declare(varname.clone(), counter);
+        self.analyzer.declare_var(varname, stype);
+        let body = self.compile_loop_body(block, latch_bb, exit_bb);
+        self.exit_scope();
+        body?;
+
+        self.builder.position_at_end(latch_bb);
+        let current = self
+            .builder
+            .build_load(counter, varname)
+            .unwrap()
+            .into_int_value();
+        if inclusive {
+            //stepping past the end of an inclusive range could overflow, so leave before it
+            let step_bb = self.context.append_basic_block(function, "forstep");
+            let last = self
+                .builder
+                .build_int_compare(IntPredicate::EQ, current, end, "forlast")
+                .unwrap();
+            self.builder
+                .build_conditional_branch(last, exit_bb, step_bb)
+                .unwrap();
+            self.builder.position_at_end(step_bb);
+        }
+        let next = self
+            .builder
+            .build_int_add(current, current.get_type().const_int(1, false), "fornext")
+            .unwrap();
+        self.builder.build_store(counter, next).unwrap();
+        self.builder.build_unconditional_branch(cond_bb).unwrap();
+
+        self.builder.position_at_end(exit_bb);
+        Ok(None)
+    }
+    fn build_entry_alloca(&self, btype: BasicTypeEnum<'a>, name: &str) -> PointerValue<'a> {
+        //allocas in the entry block are done once, even when declared inside a loop
+        let builder = self.context.create_builder();
+        let entry = self.current_function().get_first_basic_block().unwrap();
+        if let Some(first) = entry.get_first_instruction() {
+            builder.position_before(&first);
+        } else {
+            builder.position_at_end(entry);
+        }
+        builder.build_alloca(btype, name).unwrap()
+    }
+    fn compile_loop_body(
+        &mut self,
+        block: Expression,
+        continue_block: BasicBlock<'a>,
+        break_block: BasicBlock<'a>,
+    ) -> Result<(), CompilationError> {
+        self.loops.push(LoopBlocks {
+            continue_block,
+            break_block,
+        });
+        self.analyzer.enter_loop();
+        let body = self.compile_ast(block);
+        self.analyzer.exit_loop();
+        self.loops.pop();
+        body?;
+        if !self.is_terminated() {
+            self.builder
+                .build_unconditional_branch(continue_block)
+                .unwrap();
+        }
+        Ok(())
+    }
+    fn compile_loop_jump(&mut self, expr: &Expression) -> Result<(), CompilationError> {
+        self.analyzer.analyze_expr(expr)?;
+        let blocks = self.loops.last().unwrap();
+        let target = match expr.kind {
+            ExpressionKind::Continue => blocks.continue_block,
+            _ => blocks.break_block,
         };
-        Ok(f)
+        self.builder.build_unconditional_branch(target).unwrap();
+        Ok(())
+    }
+    fn is_terminated(&self) -> bool {
+        self.builder
+            .get_insert_block()
+            .and_then(|block| block.get_terminator())
+            .is_some()
+    }
+    fn compile_call(
+        &mut self,
+        identifier: String,
+        args: Vec<Expression>,
+        span: Span,
+    ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
+        let function =
+            self.module
+                .get_function(&identifier)
+                .ok_or(CompilationError::UndeclaredVariable(
+                    identifier.clone(),
+                    span,
+                ))?;
+        let SemanticType::FnType { params, .. } =
+            self.analyzer.analyze_var(&identifier, span)?.clone()
+        else {
+            return Err(SemanticError::NotCallable(identifier, span).into());
+        };
+        let mut arguments = Vec::with_capacity(args.len());
+        for (arg, param) in args.into_iter().zip(&params) {
+            let Some(arg) = self.compile_expecting(arg, param)? else {
+                if self.is_terminated() {
+                    //the argument jumped away, so the call is never made
+                    return Ok(None);
+                }
+                return Err(CompilationError::TryingAssignVoid);
+            };
+            arguments.push(arg.into());
+        }
+        Ok(self
+            .builder
+            .build_call(function, &arguments, "call")
+            .unwrap()
+            .try_as_basic_value()
+            .left())
     }
     fn compile_block(
         &mut self,
         mut exprs: Vec<Expression>,
+        expected: Option<&SemanticType>,
     ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
         if exprs.len() > 0 {
+            self.enter_scope();
             let last = exprs.pop().unwrap();
-            for expr in exprs {
-                self.compile_ast(expr)?;
-            }
-            self.compile_ast(last)
+            let value = self.compile_scoped_block(exprs, last, expected);
+            self.exit_scope();
+            value
         } else {
             Ok(None)
         }
     }
+    fn compile_scoped_block(
+        &mut self,
+        exprs: Vec<Expression>,
+        last: Expression,
+        expected: Option<&SemanticType>,
+    ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
+        for expr in exprs {
+            self.compile_ast(expr)?;
+            if self.is_terminated() {
+                //anything after a jump is unreachable
+                return Ok(None);
+            }
+        }
+        self.compile_tail(last, expected)
+    }
+    //compiles an expression whose type may come from its context, see SemanticAnalayzer::literal_type
+    fn compile_expecting(
+        &mut self,
+        expr: Expression,
+        expected: &SemanticType,
+    ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
+        //blocks and ifs pass the expected type on to the value they yield, as in the analyzer
+        match expr.kind {
+            ExpressionKind::Block(exprs) => return self.compile_block(exprs, Some(expected)),
+            ExpressionKind::If {
+                ref condition,
+                ref then_block,
+                ref else_block,
+            } => {
+                let stype = self.analyzer.analyze_expr_expecting(&expr, expected)?;
+                return self.compile_if(
+                    *condition.clone(),
+                    *then_block.clone(),
+                    else_block.clone().map(|e| *e),
+                    stype,
+                    Some(expected),
+                );
+            }
+            _ => {}
+        }
+        let Some(stype) = SemanticAnalayzer::literal_type(&expr, expected) else {
+            return self.compile_ast(expr);
+        };
+        match expr.kind {
+            ExpressionKind::Negative(inner) => self.compile_negative(*inner, &stype),
+            _ => Ok(Some(self.compile_literal(&expr, &stype))),
+        }
+    }
+    fn compile_tail(
+        &mut self,
+        expr: Expression,
+        expected: Option<&SemanticType>,
+    ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
+        match expected {
+            Some(expected) => self.compile_expecting(expr, expected),
+            None => self.compile_ast(expr),
+        }
+    }
+    //the analyzer already checked that the literal fits in its type
+    fn compile_literal(&self, expr: &Expression, stype: &SemanticType) -> BasicValueEnum<'a> {
+        let btype = self.basic_type_from_stype(stype).unwrap();
+        match &expr.kind {
+            ExpressionKind::IntLit(lit) => {
+                let (digits, _) = SemanticAnalayzer::split_literal(lit);
+                btype
+                    .into_int_type()
+                    .const_int(digits.parse::<u64>().unwrap(), false)
+                    .as_basic_value_enum()
+            }
+            ExpressionKind::FloatLit(lit) => {
+                let (digits, _) = SemanticAnalayzer::split_literal(lit);
+                btype
+                    .into_float_type()
+                    .const_float(digits.parse::<f64>().unwrap())
+                    .as_basic_value_enum()
+            }
+            _ => unreachable!(),
+        }
+    }
+    //the bytes are kept in a private constant global, and the value points to them
+    fn compile_str(&self, s: &str) -> BasicValueEnum<'a> {
+        let bytes = self.context.const_string(s.as_bytes(), false);
+        let global = self.module.add_global(bytes.get_type(), None, "str");
+        global.set_initializer(&bytes);
+        global.set_constant(true);
+        global.set_linkage(Linkage::Private);
+        global.set_unnamed_addr(true);
+        let ptr = global
+            .as_pointer_value()
+            .const_cast(self.context.i8_type().ptr_type(AddressSpace::default()));
+        let len = self.int(64).const_int(s.len() as u64, false);
+        self.str()
+            .const_named_struct(&[ptr.into(), len.into()])
+            .as_basic_value_enum()
+    }
     fn compile_negative(
         &mut self,
         expr: Expression,
+        stype: &SemanticType,
     ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
-        if let Expression::Negative(neg) = expr {
-            return self.compile_ast(*neg);
+        if let ExpressionKind::Negative(neg) = expr.kind {
+            return self.compile_expecting(*neg, stype);
         }
-        let val = self.compile_ast(expr.clone())?.unwrap(); //no sense to be void
+        let val = match expr.kind {
+            //checked by the analyzer along with its negation
+            ExpressionKind::IntLit(..) | ExpressionKind::FloatLit(..) => {
+                self.compile_literal(&expr, stype)
+            }
+            _ => self.compile_expecting(expr.clone(), stype)?.unwrap(), //no sense to be void
+        };
         if val.is_int_value() {
             Ok(Some(
                 self.builder
@@ -274,40 +873,133 @@ impl<'a> CodeGenerator<'a> {
             Err(CompilationError::InvalidNegation(expr))
         }
     }
+    fn compile_cast(
+        &mut self,
+        expr: Expression,
+        from: &SemanticType,
+        to: &SemanticType,
+    ) -> Result<BasicValueEnum<'a>, CompilationError> {
+        let value = self.compile_expecting(expr, from)?.unwrap();
+        if from == to {
+            return Ok(value);
+        }
+        let target = self.basic_type_from_stype(to).unwrap();
+        Ok(match (from.is_float(), to.is_float()) {
+            (false, false) => {
+                let value = value.into_int_value();
+                let target = target.into_int_type();
+                let width = |stype: &SemanticType| match stype {
+                    SemanticType::Bool => 1,
+                    SemanticType::Char => 32,
+                    stype => stype.int_width().unwrap(),
+                };
+                let from_width = width(from);
+                let to_width = width(to);
+                if to_width < from_width {
+                    self.builder.build_int_truncate(value, target, "trunc")
+                } else if to_width > from_width && from.is_signed() {
+                    self.builder.build_int_s_extend(value, target, "sext")
+                } else if to_width > from_width {
+                    self.builder.build_int_z_extend(value, target, "zext")
+                } else {
+                    return Ok(value.as_basic_value_enum());
+                }
+                .unwrap()
+                .as_basic_value_enum()
+            }
+            (false, true) => {
+                let value = value.into_int_value();
+                let target = target.into_float_type();
+                if from.is_signed() {
+                    self.builder
+                        .build_signed_int_to_float(value, target, "sitofp")
+                } else {
+                    self.builder
+                        .build_unsigned_int_to_float(value, target, "uitofp")
+                }
+                .unwrap()
+                .as_basic_value_enum()
+            }
+            (true, false) => {
+                let value = value.into_float_value();
+                let target = target.into_int_type();
+                if to.is_signed() {
+                    self.builder
+                        .build_float_to_signed_int(value, target, "fptosi")
+                } else {
+                    self.builder
+                        .build_float_to_unsigned_int(value, target, "fptoui")
+                }
+                .unwrap()
+                .as_basic_value_enum()
+            }
+            (true, true) => {
+                let value = value.into_float_value();
+                let target = target.into_float_type();
+                match (from, to) {
+                    (SemanticType::Float32, SemanticType::Float64) => {
+                        self.builder.build_float_ext(value, target, "fpext")
+                    }
+                    (SemanticType::Float64, SemanticType::Float32) => {
+                        self.builder.build_float_trunc(value, target, "fptrunc")
+                    }
+                    _ => return Ok(value.as_basic_value_enum()),
+                }
+                .unwrap()
+                .as_basic_value_enum()
+            }
+        })
+    }
     fn compile_vardecl(
         &mut self,
-        _kind: LetDeclKind,
+        kind: LetDeclKind,
         varname: &str,
         expr: Expression,
-    ) -> Result<PointerValue<'a>, CompilationError> {
-        let stype = self.type_based_on_semantics(&expr)?;
-        let alloc = self
-            .builder
-            .build_alloca(
-                if let Some(stype) = stype {
-                    match stype {
-                        CodeGenType::Fn(f) => {
-                            f.ptr_type(AddressSpace::default()).as_basic_type_enum()
-                        }
-                        CodeGenType::Primitive(basic) => basic,
-                    }
-                } else {
-                    return Err(CompilationError::TryingAssignVoid);
-                },
-                varname,
-            )
-            .unwrap();
-        let variable = varname.to_owned();
-        self.analyzer
-            .create_var(&variable, &expr)
-            .map_err(CompilationError::TypeError)?;
+    ) -> Result<Option<PointerValue<'a>>, CompilationError> {
+        let stype = self.analyzer.analyze_expr(&expr)?;
+        let Some(btype) = self.basic_type_from_stype(&stype) else {
+            if stype == SemanticType::Never {
+                //the initializer jumps away before the binding exists
+                self.compile_ast(expr)?;
+                return Ok(None);
+            }
+            return Err(CompilationError::TryingAssignVoid);
+        };
+        let alloc = self.build_entry_alloca(btype, varname);
+        //the initializer may still refer to a variable this one shadows, so the new binding is
+        //only declared once it is compiled
         if let Some(expression) = self.compile_ast(expr)? {
             self.builder.build_store(alloc, expression).unwrap();
-        } else {
-            self.analyzer.delete_var(&variable);
         }
-        self.variables.insert(varname.to_string(), alloc);
-        Ok(alloc)
+        self.analyzer.bind_var(&varname.to_string(), stype, &kind);
+        self.declare(varname.to_string(), alloc);
+        Ok(Some(alloc))
+    }
+    fn int_predicate(operator: Operator, signed: bool) -> Option<IntPredicate> {
+        Some(match (operator, signed) {
+            (Operator::EqEq, _) => IntPredicate::EQ,
+            (Operator::NotEq, _) => IntPredicate::NE,
+            (Operator::Less, true) => IntPredicate::SLT,
+            (Operator::LessEq, true) => IntPredicate::SLE,
+            (Operator::Greater, true) => IntPredicate::SGT,
+            (Operator::GreaterEq, true) => IntPredicate::SGE,
+            (Operator::Less, false) => IntPredicate::ULT,
+            (Operator::LessEq, false) => IntPredicate::ULE,
+            (Operator::Greater, false) => IntPredicate::UGT,
+            (Operator::GreaterEq, false) => IntPredicate::UGE,
+            _ => return None,
+        })
+    }
+    fn float_predicate(operator: Operator) -> Option<FloatPredicate> {
+        Some(match operator {
+            Operator::EqEq => FloatPredicate::OEQ,
+            Operator::NotEq => FloatPredicate::UNE,
+            Operator::Less => FloatPredicate::OLT,
+            Operator::LessEq => FloatPredicate::OLE,
+            Operator::Greater => FloatPredicate::OGT,
+            Operator::GreaterEq => FloatPredicate::OGE,
+            _ => return None,
+        })
     }
     fn compile_binexpr(
         &mut self,
@@ -315,13 +1007,31 @@ impl<'a> CodeGenerator<'a> {
         rhs: Box<Expression>,
         operator: Operator,
         stype: SemanticType,
-    ) -> Result<BasicValueEnum<'a>, CompilationError> {
-        let lhs = self.compile_ast(*lhs)?.unwrap();
-        let rhs = self.compile_ast(*rhs)?.unwrap();
-        Ok(match stype {
-            SemanticType::Int32 => {
+    ) -> Result<Option<BasicValueEnum<'a>>, CompilationError> {
+        let span = lhs.span.to(rhs.span);
+        //an operand that jumps away leaves nothing to compute
+        let Some(lhs) = self.compile_expecting(*lhs, &stype)? else {
+            return Ok(None);
+        };
+        let Some(rhs) = self.compile_expecting(*rhs, &stype)? else {
+            return Ok(None);
+        };
+        Ok(Some(match stype {
+            stype
+                if stype.is_integer()
+                    || matches!(stype, SemanticType::Bool | SemanticType::Char) =>
+            {
+                let signed = stype.is_signed();
                 let lhs = lhs.into_int_value();
                 let rhs = rhs.into_int_value();
+                if let Some(predicate) = Self::int_predicate(operator, signed) {
+                    return Ok(Some(
+                        self.builder
+                            .build_int_compare(predicate, lhs, rhs, "comparison")
+                            .unwrap()
+                            .as_basic_value_enum(),
+                    ));
+                }
                 match operator {
                     Operator::Plus => self.builder.build_int_add(lhs, rhs, "addition").unwrap(),
                     Operator::Minus => self.builder.build_int_sub(lhs, rhs, "subtraction").unwrap(),
@@ -329,17 +1039,46 @@ impl<'a> CodeGenerator<'a> {
                         .builder
                         .build_int_mul(lhs, rhs, "multiplication")
                         .unwrap(),
-                    Operator::Bar => self
+                    Operator::Bar if signed => self
                         .builder
                         .build_int_signed_div(lhs, rhs, "division")
                         .unwrap(),
+                    Operator::Bar => self
+                        .builder
+                        .build_int_unsigned_div(lhs, rhs, "division")
+                        .unwrap(),
+                    Operator::Percent if signed => self
+                        .builder
+                        .build_int_signed_rem(lhs, rhs, "remainder")
+                        .unwrap(),
+                    Operator::Percent => self
+                        .builder
+                        .build_int_unsigned_rem(lhs, rhs, "remainder")
+                        .unwrap(),
+                    Operator::Ampersand => self.builder.build_and(lhs, rhs, "and").unwrap(),
+                    Operator::Pipe => self.builder.build_or(lhs, rhs, "or").unwrap(),
+                    Operator::Caret => self.builder.build_xor(lhs, rhs, "xor").unwrap(),
+                    Operator::ShiftLeft => self.builder.build_left_shift(lhs, rhs, "shl").unwrap(),
+                    //arithmetic shift for signed integers, logical for unsigned ones
+                    Operator::ShiftRight => self
+                        .builder
+                        .build_right_shift(lhs, rhs, signed, "shr")
+                        .unwrap(),
                     _ => panic!("{operator:?} is invalid or gotta be implemented"),
                 }
                 .as_basic_value_enum()
             }
-            SemanticType::Float32 => {
+            stype if stype.is_float() => {
                 let lhs = lhs.into_float_value();
                 let rhs = rhs.into_float_value();
+                if let Some(predicate) = Self::float_predicate(operator) {
+                    return Ok(Some(
+                        self.builder
+                            .build_float_compare(predicate, lhs, rhs, "comparison")
+                            .unwrap()
+                            .as_basic_value_enum(),
+                    ));
+                }
                 match operator {
                     Operator::Plus => self.builder.build_float_add(lhs, rhs, "addition").unwrap(),
                     Operator::Minus => self
@@ -351,16 +1090,587 @@ impl<'a> CodeGenerator<'a> {
                         .build_float_mul(lhs, rhs, "multiplication")
                         .unwrap(),
                     Operator::Bar => self.builder.build_float_div(lhs, rhs, "division").unwrap(),
+                    Operator::Percent => {
+                        self.builder.build_float_rem(lhs, rhs, "remainder").unwrap()
+                    }
                     _ => panic!("{operator:?} is invalid or gotta be implemented"),
                 }
                 .as_basic_value_enum()
             }
             t => {
-                return Err(CompilationError::TypeError(SemanticError::InvalidBinExpr {
+                return Err(SemanticError::InvalidBinExpr {
                     lhs_type: t.clone(),
                     rhs_type: t,
-                }));
+                    span,
+                }
+                .into());
             }
-        })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::errors::LitParseError;
+    use inkwell::OptimizationLevel;
+
+    //compiles the source and runs its main function, which must return an int32
+    fn run(source: &str) -> Result<i32, CompilationError> {
+        let ctx = CodeGenerator::create_ctx();
+        let mut generator = CodeGenerator::new(&ctx);
+        generator.compile_source(source.to_string(), None)?;
+        generator.module.verify().unwrap();
+        let engine = generator
+            .module
+            .create_jit_execution_engine(OptimizationLevel::None)
+            .unwrap();
+        unsafe {
+            let main = engine
+                .get_function::<unsafe extern "C" fn() -> i32>("main")
+                .unwrap();
+            Ok(main.call())
+        }
+    }
+
+    //runs an expression of type int32 as the body of main
+    fn eval(body: &str) -> Result<i32, CompilationError> {
+        run(&format!("func main(): int32 {{ {body} }}"))
+    }
+
+    //the textual IR of the module compiled from the source
+    fn ir(source: &str) -> String {
+        let ctx = CodeGenerator::create_ctx();
+        let mut generator = CodeGenerator::new(&ctx);
+        generator.compile_source(source.to_string(), None).unwrap();
+        generator.module.print_to_string().to_string()
+    }
+
+    fn out_of_range(body: &str) -> bool {
+        matches!(
+            eval(body),
+            Err(CompilationError::LitParseError(
+                LitParseError::OutOfRange(_),
+                _
+            ))
+        )
+    }
+
+    #[test]
+    fn sized_literals_in_range() {
+        assert_eq!(eval("(127i8 as int32) + (-128i8 as int32)").unwrap(), -1);
+        assert_eq!(eval("255u8 as int32").unwrap(), 255);
+        assert_eq!(eval("(65535u16 as int32) - 65535").unwrap(), 0);
+        assert_eq!(eval("2147483647").unwrap(), i32::MAX);
+        assert_eq!(eval("-2147483648").unwrap(), i32::MIN);
+        assert_eq!(
+            eval("(4294967295u32 == 0xffff_ffffu32) as int32").unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn sized_literals_out_of_range() {
+        for body in [
+            "128i8; 0",
+            "-129i8; 0",
+            "256u8; 0",
+            "65536u16; 0",
+            "2147483648",
+            "-2147483649",
+            "18446744073709551616u64; 0",
+            "1e39f32; 0",
+        ] {
+            assert!(out_of_range(body), "{body}");
+        }
+    }
+
+    #[test]
+    fn unsuffixed_literals_take_the_expected_range() {
+        let source = "func f(x: int8): int32 { x as int32 }
+            func main(): int32 { f(200) }";
+        assert!(matches!(
+            run(source),
+            Err(CompilationError::LitParseError(
+                LitParseError::OutOfRange(SemanticType::Int8),
+                _
+            ))
+        ));
+        assert!(out_of_range("let x = 1u8; x + 256; 0"));
+        assert_eq!(eval("let x = 1u8; (x + 254) as int32").unwrap(), 255);
+    }
+
+    #[test]
+    fn unsuffixed_literals_in_tails_and_branches() {
+        let source = "func five(): int64 { 5 }
+            func fact(n: int64): int64 { if n <= 1 { 1 } else { n * fact(n - 1) } }
+            func byte(high: bool): uint8 { if high { { 255 } } else { return 1; } }
+            func main(): int32 {
+                (fact(20) / fact(18) + five()) as int32 + byte(true) as int32
+            }";
+        assert_eq!(run(source).unwrap(), 380 + 5 + 255);
+        assert!(matches!(
+            run("func f(): uint8 { if true { 256 } else { 0 } } func main(): int32 { 0 }"),
+            Err(CompilationError::LitParseError(
+                LitParseError::OutOfRange(SemanticType::Uint8),
+                _
+            ))
+        ));
+    }
+
+    #[test]
+    fn unsigned_arithmetic() {
+        let cases = [
+            ("(255u8 / 2u8) as int32", 127),
+            ("(250u8 % 7u8) as int32", 5),
+            ("(200u8 < 100u8) as int32", 0),
+            ("(-56i8 < 100i8) as int32", 1),
+            ("(4000000000u32 / 2u32) as int32", 2000000000),
+            ("200u8 as int32", 200),
+            ("-56i8 as int32", -56),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(eval(body).unwrap(), expected, "{body}");
+        }
+    }
+
+    #[test]
+    fn signedness_picks_the_instruction() {
+        let unsigned = ir("func f(a: uint16; b: uint16): bool {
+            let q = a / b; let r = a % b; (q as uint64) < (r as uint64)
+        }");
+        for instruction in ["udiv i16", "urem i16", "zext i16", "icmp ult i64"] {
+            assert!(
+                unsigned.contains(instruction),
+                "{instruction} in {unsigned}"
+            );
+        }
+        let signed = ir("func f(a: int16; b: int16): bool {
+            let q = a / b; let r = a % b; (q as int64) < (r as int64)
+        }");
+        for instruction in ["sdiv i16", "srem i16", "sext i16", "icmp slt i64"] {
+            assert!(signed.contains(instruction), "{instruction} in {signed}");
+        }
+    }
+
+    #[test]
+    fn numeric_casts() {
+        let cases = [
+            ("300 as int8 as int32", 44),
+            ("-1 as uint8 as int32", 255),
+            ("-1i8 as uint8 as int32", 255),
+            ("255u8 as int8 as int32", -1),
+            ("-1i8 as int64 as int32", -1),
+            ("3.9 as int32", 3),
+            ("-3.9f64 as int32", -3),
+            ("(7 as f64 / 2 as f64 * 2.0) as int32", 7),
+            ("1.5f64 as f32 as int32", 1),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(eval(body).unwrap(), expected, "{body}");
+        }
+    }
+
+    #[test]
+    fn bool_and_char_casts() {
+        let cases = [
+            ("true as int32 + false as int32", 1),
+            ("'A' as int32", 65),
+            ("'é' as uint8 as int32", 0xe9),
+            ("65u8 as char as int32", 65),
+            ("(97 as char == 'a') as int32", 1),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(eval(body).unwrap(), expected, "{body}");
+        }
+    }
+
+    #[test]
+    fn invalid_casts() {
+        for body in [
+            "true as f32; 0",
+            "1 as bool; 0",
+            "'a' as f32; 0",
+            "1.5 as char; 0",
+            "70i32 as char; 0",
+            "\"s\" as int32",
+        ] {
+            assert!(
+                matches!(
+                    eval(body),
+                    Err(CompilationError::TypeError(
+                        SemanticError::InvalidCast { .. }
+                    ))
+                ),
+                "{body}"
+            );
+        }
+    }
+
+    #[test]
+    fn if_yields_the_taken_branch() {
+        let source = "func pick(c: bool; a: int32; b: int32): int32 { if c { a } else { b } }
+            func main(): int32 {
+                let x = if pick(true, 1, 2) == 1 { 10 } else { 20 };
+                x + pick(false, 1, 2)
+            }";
+        assert_eq!(run(source).unwrap(), 12);
+        assert!(ir(source).contains("phi i32"));
+        assert!(matches!(
+            eval("if true { 1 } else { false }"),
+            Err(CompilationError::TypeError(
+                SemanticError::MismatchedBranches {
+                    then_type: SemanticType::Int32,
+                    else_type: SemanticType::Bool,
+                    ..
+                }
+            ))
+        ));
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        let body = "let mut runs = 0;
+            let a = false && { runs += 1; true };
+            let b = true || { runs += 10; false };
+            let c = true && { runs += 100; true };
+            let d = false || { runs += 1000; false };
+            if !a && b && c && !d { runs } else { -1 }";
+        assert_eq!(eval(body).unwrap(), 1100);
+        assert!(matches!(
+            eval("1 && 2; 0"),
+            Err(CompilationError::TypeError(
+                SemanticError::InvalidOperator {
+                    operator: Operator::And,
+                    ..
+                }
+            ))
+        ));
+        assert!(matches!(
+            eval("!1; 0"),
+            Err(CompilationError::TypeError(
+                SemanticError::InvalidOperator {
+                    operator: Operator::Not,
+                    ..
+                }
+            ))
+        ));
+    }
+
+    #[test]
+    fn loops_break_and_continue() {
+        let body = "let mut i = 0;
+            let mut odd = 0;
+            while true {
+                i += 1;
+                if i > 10 { break; };
+                if i % 2 == 0 { continue; };
+                odd += i;
+            };
+            let mut skipped = 0;
+            for j in 0..10 { if j < 7 { continue; }; skipped += j; };
+            odd * 100 + skipped";
+        assert_eq!(eval(body).unwrap(), 25 * 100 + 7 + 8 + 9);
+        assert!(matches!(
+            eval("break; 0"),
+            Err(CompilationError::TypeError(
+                SemanticError::BreakOutsideLoop(_)
+            ))
+        ));
+        assert!(matches!(
+            eval("if true { continue; }; 0"),
+            Err(CompilationError::TypeError(
+                SemanticError::ContinueOutsideLoop(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn function_calls() {
+        let source = "func add(a: int32; b: int32): int32 = a + b;
+            func nothing() {}
+            func main(): int32 { nothing(); add(2, add(3, 4)) }";
+        assert_eq!(run(source).unwrap(), 9);
+    }
+
+    #[test]
+    fn invalid_calls() {
+        let source = "func add(a: int32; b: int32): int32 = a + b;
+            func main(): int32 { add(1) }";
+        assert!(matches!(
+            run(source),
+            Err(CompilationError::TypeError(
+                SemanticError::InvalidArgCount {
+                    expected: 2,
+                    received: 1,
+                    ..
+                }
+            ))
+        ));
+        let source = "func add(a: int32; b: int32): int32 = a + b;
+            func main(): int32 { add(1, true) }";
+        assert!(matches!(
+            run(source),
+            Err(CompilationError::TypeError(SemanticError::InvalidArgType {
+                expected: SemanticType::Int32,
+                received: SemanticType::Bool,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            eval("let x = 1; x(2)"),
+            Err(CompilationError::TypeError(SemanticError::NotCallable(..)))
+        ));
+    }
+
+    #[test]
+    fn float_literals_take_the_return_type() {
+        let source = "func half(): f64 = 0.5;
+            func block(): f64 { 1.5 }
+            func branch(big: bool): f64 { if big { 1e300 } else { { 2.25 } } }
+            func main(): int32 {
+                let x = half() + block() + branch(false);
+                if branch(true) > 1e200f64 { (x * 4.0) as int32 } else { 0 }
+            }";
+        assert_eq!(run(source).unwrap(), 17);
+    }
+
+    #[test]
+    fn early_returns() {
+        let source = "func find(limit: int32): int32 {
+                for i in 0..limit {
+                    while true {
+                        if i * i > 50 { { return i; } };
+                        break;
+                    };
+                };
+                -1
+            }
+            func sign(x: int32): int32 {
+                if x < 0 { return -1; };
+                if x == 0 { return 0; } else { return 1; }
+            }
+            func main(): int32 { find(100) * 10 + find(3) + sign(-5) + sign(0) + sign(9) }";
+        assert_eq!(run(source).unwrap(), 80 - 1 - 1 + 1);
+        assert!(matches!(
+            eval("return true;"),
+            Err(CompilationError::TypeError(
+                SemanticError::InvalidReturnType {
+                    expected: SemanticType::Int32,
+                    received: SemanticType::Bool,
+                    ..
+                }
+            ))
+        ));
+    }
+
+    #[test]
+    fn assignments() {
+        let body = "let mut x = 1; x = x + 41; let y = { x = 2; x }; x + y";
+        assert_eq!(eval(body).unwrap(), 4);
+        assert!(matches!(
+            eval("let x = 1; x = 2; x"),
+            Err(CompilationError::TypeError(
+                SemanticError::AssignToImmutable(..)
+            ))
+        ));
+        assert!(matches!(
+            eval("let mut x = 1; x = true; x"),
+            Err(CompilationError::TypeError(
+                SemanticError::InvalidAssignment {
+                    var_type: SemanticType::Int32,
+                    expr_type: SemanticType::Bool,
+                    ..
+                }
+            ))
+        ));
+        let source = "func f(p: int32): int32 { p = 1; p } func main(): int32 { f(0) }";
+        assert!(matches!(
+            run(source),
+            Err(CompilationError::TypeError(
+                SemanticError::AssignToImmutable(..)
+            ))
+        ));
+    }
+
+    #[test]
+    fn compound_assignments() {
+        let body = "let mut x = 10; x += 5; x -= 3; x *= 4; x /= 6; x %= 5; x";
+        assert_eq!(eval(body).unwrap(), 3);
+        let body = "let mut f = 1.5f64; f *= 4.0; f -= 0.5; f as int32";
+        assert_eq!(eval(body).unwrap(), 5);
+        assert!(matches!(
+            eval("let x = 1; x += 1; x"),
+            Err(CompilationError::TypeError(
+                SemanticError::AssignToImmutable(..)
+            ))
+        ));
+        assert!(matches!(
+            eval("let mut x = 1; x += true; x"),
+            Err(CompilationError::TypeError(
+                SemanticError::InvalidBinExpr { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn forward_and_mutual_recursion() {
+        let source =
+            "func main(): int32 { fib(10) + is_even(7) as int32 * 100 + is_odd(7) as int32 }
+            func fib(n: int32): int32 { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }
+            func is_even(n: int32): bool { if n == 0 { true } else { is_odd(n - 1) } }
+            func is_odd(n: int32): bool { if n == 0 { false } else { is_even(n - 1) } }";
+        assert_eq!(run(source).unwrap(), 55 + 1);
+        assert!(matches!(
+            run("func f() {} func f() {} func main(): int32 { 0 }"),
+            Err(CompilationError::TypeError(
+                SemanticError::FunctionRedeclare(..)
+            ))
+        ));
+    }
+
+    #[test]
+    fn bitwise_and_shift_operators() {
+        let cases = [
+            ("-7 % 3", -1),
+            ("(7.5 % 2.0) as int32", 1),
+            ("12 & 10 | 1", 9),
+            ("12 ^ 10", 6),
+            ("~5", -6),
+            ("1 << 4 + 1", 32),
+            ("-16 >> 2", -4),
+            ("(0xf0u8 >> 4) as int32", 15),
+            (
+                "let mut x = 6; x &= 3; x |= 8; x ^= 1; x <<= 2; x >>= 1; x",
+                22,
+            ),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(eval(body).unwrap(), expected, "{body}");
+        }
+        let shifts = ir("func f(a: int8; b: uint8): uint8 { ((a >> 1) as uint8) ^ (b >> 1) }");
+        assert!(shifts.contains("ashr i8") && shifts.contains("lshr i8"));
+        for body in [
+            "1.5 & 2.0; 0",
+            "true | false; 0",
+            "~1.5; 0",
+            "1.0 << 2.0; 0",
+        ] {
+            assert!(
+                matches!(
+                    eval(body),
+                    Err(CompilationError::TypeError(
+                        SemanticError::InvalidOperator { .. }
+                    ))
+                ),
+                "{body}"
+            );
+        }
+    }
+
+    #[test]
+    fn diverging_operands() {
+        let cases = [
+            ("let y = 1 + { return 2; 3 }; y", 2),
+            ("let y = ({ return 3; 4 }) * 5; y", 3),
+            ("let y = { return 4; 5 }; y", 4),
+            ("if false || { return 5; true } { 6 } else { 7 }", 5),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(eval(body).unwrap(), expected, "{body}");
+        }
+        let source = "func id(x: int32): int32 = x;
+            func main(): int32 { id({ return 6; 7 }) }";
+        assert_eq!(run(source).unwrap(), 6);
+    }
+
+    #[test]
+    fn shadowing_ends_with_its_scope() {
+        let body = "let x = 1;
+            { let x = true; let x = 2.5; };
+            let y = { let x = x + 1; x };
+            x + y";
+        assert_eq!(eval(body).unwrap(), 3);
+    }
+
+    #[test]
+    fn shadowing_with_another_type() {
+        let source = "func main(): int32 {
+            let x = 5;
+            let x = x == 5;
+            let y = { let x = 7; let x = x as int64; x };
+            { let x = y as f32; };
+            if x { y as int32 } else { 0 }
+        }";
+        assert_eq!(run(source).unwrap(), 7);
+    }
+
+    #[test]
+    fn range_bounds_take_the_type_of_the_other_bound() {
+        let source = "func count(n: int64): int64 {
+            let mut total = 0i64;
+            for i in 0..n { total += i; };
+            total
+        }
+        func main(): int32 {
+            let mut bytes = 0;
+            for b in 250..=255u8 { bytes += 1; };
+            for i in -2..2 { bytes += 1; };
+            count(5) as int32 + bytes
+        }";
+        assert_eq!(run(source).unwrap(), 20);
+    }
+
+    #[test]
+    fn casts_to_the_same_type() {
+        let source = "func main(): int32 {
+            let c = 'a' as char;
+            let s = \"hi\" as str;
+            if true as bool { (c as int32) + (65 as char as int32) } else { 0 }
+        }";
+        assert_eq!(run(source).unwrap(), 97 + 65);
+    }
+
+    #[test]
+    fn semantic_errors_point_at_their_expression() {
+        let cases = [
+            ("func main(): int32 { y }", "y"),
+            ("func main(): int32 { 1 + true }", "1 + true"),
+            ("func main(): int32 { if 1 { 2 } else { 3 } }", "1"),
+            ("func main(): int32 { for i in 0..1.5 {}; 0 }", "0..1.5"),
+            (
+                "func f(x: int8) {} func main(): int32 { f(true); 0 }",
+                "true",
+            ),
+            ("func main(): int32 { break; 0 }", "break"),
+            ("func main(): int32 { true }", "func main(): int32 { true }"),
+            (
+                "func f(x: foo) {} func main(): int32 { 0 }",
+                "func f(x: foo) {}",
+            ),
+        ];
+        for (source, expected) in cases {
+            let Err(CompilationError::TypeError(error)) = run(source) else {
+                panic!("{source:?} should not compile");
+            };
+            let span = error.span();
+            assert_eq!(&source[span.start..span.end], expected, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn nested_functions_are_rejected() {
+        for source in [
+            "func main(): int32 { func g(): int32 = 4; 5 }",
+            "func main(): int32 { { func g() {} }; 5 }",
+            "{ func g() {} };",
+        ] {
+            assert!(matches!(
+                run(source),
+                Err(CompilationError::TypeError(SemanticError::NestedFunction(
+                    ..
+                )))
+            ));
+        }
     }
 }
@@ -1,39 +1,42 @@
-use std::{
-    collections::VecDeque,
-    num::{ParseFloatError, ParseIntError},
-};
+use std::collections::VecDeque;
 
 use parser::{
     parsing::{Expression, ParseStep, ParsingError},
-    tokenizer::TokenizationError,
+    tokenizer::{Span, TokenizationError},
 };
 
-use crate::analysis::errors::SemanticError;
-
-#[derive(Debug, Clone)]
-pub enum LitParseError {
-    Int(ParseIntError),
-    Float(ParseFloatError),
-}
+use crate::analysis::errors::{LitParseError, SemanticError};
 
 #[derive(Debug, Clone)]
 pub enum CompilationError {
     Tokenization(TokenizationError),
     Parsing(ParsingError, VecDeque<ParseStep>),
     TypeError(SemanticError),
-    LitParseError(LitParseError),
-    UndeclaredVariable(String),
+    LitParseError(LitParseError, Span),
+    UndeclaredVariable(String, Span),
     InvalidNegation(Expression),
-    InvalidRedeclare(String),
+    InvalidRedeclare(String, Span),
     TryingAssignVoid,
 }
+impl From<SemanticError> for CompilationError {
+    fn from(error: SemanticError) -> Self {
+        match error {
+            SemanticError::InvalidLiteral { error, span } => {
+                CompilationError::LitParseError(error, span)
+            }
+            error => CompilationError::TypeError(error),
+        }
+    }
+}
 impl std::fmt::Display for CompilationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            CompilationError::InvalidRedeclare(e) => {
-                write!(f, "Cannot redeclare variable called {e}")
-            }
-            CompilationError::Tokenization(e) => write!(f, "Tokenization Error: {e:?}"),
+            CompilationError::InvalidRedeclare(e, span) => write!(
+                f,
+                "Cannot redeclare variable called {e} at bytes <{}..{}>",
+                span.start, span.end
+            ),
+            CompilationError::Tokenization(e) => write!(f, "Tokenization Error: {e}"),
             CompilationError::Parsing(e, backtrace) => {
                 write!(f, "Parsing error: {e:?}\n Parsing Backtrace: [\n{}", {
                     let mut buffer = String::new();
@@ -48,9 +51,22 @@ impl std::fmt::Display for CompilationError {
                 f,
                 "DIdnt implement yet, but somewhere in the code is trying to assign to void"
             ),
-            CompilationError::LitParseError(e) => write!(f, "Invalid Literal: {e:?}"),
-            CompilationError::TypeError(e) => write!(f, "TypeError: {e:?}"),
-            CompilationError::UndeclaredVariable(v) => write!(f, "Undeclared variable named: {v}"),
+            CompilationError::LitParseError(e, span) => write!(
+                f,
+                "Invalid Literal: {e:?} at bytes <{}..{}>",
+                span.start, span.end
+            ),
+            CompilationError::TypeError(e) => write!(
+                f,
+                "TypeError: {e:?} at bytes <{}..{}>",
+                e.span().start,
+                e.span().end
+            ),
+            CompilationError::UndeclaredVariable(v, span) => write!(
+                f,
+                "Undeclared variable named: {v} at bytes <{}..{}>",
+                span.start, span.end
+            ),
             CompilationError::InvalidNegation(e) => write!(f, "Invalid use of unary operator"),
         }
     }
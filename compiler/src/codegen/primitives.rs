@@ -28,3 +28,31 @@ impl<'a> InkWellType<'a> for f32 {
         gen.f32().const_float(self as f64).as_basic_value_enum()
     }
 }
+impl<'a> InkWellType<'a> for f64 {
+    fn itype(gen: &'a CodeGenerator) -> BasicTypeEnum<'a> {
+        gen.f64().as_basic_type_enum()
+    }
+    fn const_val(self, gen: &'a CodeGenerator) -> BasicValueEnum<'a> {
+        gen.f64().const_float(self).as_basic_value_enum()
+    }
+}
+impl<'a> InkWellType<'a> for bool {
+    fn itype(gen: &'a CodeGenerator) -> BasicTypeEnum<'a> {
+        gen.bool().as_basic_type_enum()
+    }
+    fn const_val(self, gen: &'a CodeGenerator) -> BasicValueEnum<'a> {
+        gen.bool()
+            .const_int(self as u64, false)
+            .as_basic_value_enum()
+    }
+}
+impl<'a> InkWellType<'a> for char {
+    fn itype(gen: &'a CodeGenerator) -> BasicTypeEnum<'a> {
+        gen.i32().as_basic_type_enum()
+    }
+    fn const_val(self, gen: &'a CodeGenerator) -> BasicValueEnum<'a> {
+        gen.i32()
+            .const_int(self as u64, false)
+            .as_basic_value_enum()
+    }
+}
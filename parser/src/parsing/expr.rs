@@ -1,4 +1,4 @@
-use crate::tokenizer::Operator;
+use crate::tokenizer::{Operator, Span};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Param {
@@ -9,29 +9,87 @@ pub struct Param {
 #[derive(Debug, Clone)]
 pub enum LetDeclKind {
     Normal,
+    Mutable,
 }
 #[derive(Debug, Clone)]
-pub enum Expression {
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+#[derive(Debug, Clone)]
+pub enum ExpressionKind {
     Program(Vec<Expression>),
     Block(Vec<Expression>),
     FuncDecl {
+        docs: Vec<String>,
         identifier: String,
         params: Vec<Param>,
         rtype: Option<String>,
         block: Box<Expression>,
     },
     LetDecl {
+        docs: Vec<String>,
         kind: LetDeclKind,
         varname: String,
         expr: Box<Expression>,
     },
+    Assign {
+        varname: String,
+        expr: Box<Expression>,
+    },
     BinExpr {
         lhs: Box<Expression>,
         rhs: Box<Expression>,
         op: Operator,
     },
+    If {
+        condition: Box<Expression>,
+        then_block: Box<Expression>,
+        else_block: Option<Box<Expression>>,
+    },
+    While {
+        condition: Box<Expression>,
+        block: Box<Expression>,
+    },
+    For {
+        varname: String,
+        start: Box<Expression>,
+        end: Box<Expression>,
+        inclusive: bool,
+        block: Box<Expression>,
+    },
+    Break,
+    Continue,
+    Return(Option<Box<Expression>>),
+    Call {
+        identifier: String,
+        args: Vec<Expression>,
+    },
+    Cast {
+        expr: Box<Expression>,
+        to: String,
+    },
     Negative(Box<Expression>),
+    Not(Box<Expression>),
+    BitNot(Box<Expression>),
     Identifier(String),
     IntLit(String),
     FloatLit(String),
+    BoolLit(bool),
+    StrLit(String),
+    CharLit(char),
+}
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+    pub fn ends_with_block(&self) -> bool {
+        matches!(
+            self.kind,
+            ExpressionKind::Block(_)
+                | ExpressionKind::If { .. }
+                | ExpressionKind::While { .. }
+                | ExpressionKind::For { .. }
+        )
+    }
 }
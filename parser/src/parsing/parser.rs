@@ -1,8 +1,34 @@
 use std::collections::VecDeque;
 
-use crate::tokenizer::{Operator, Token, TokenKind};
+use crate::tokenizer::{Operator, Span, Token, TokenKind};
 
-use super::{Expression, LetDeclKind};
+use super::{Expression, ExpressionKind, LetDeclKind};
+
+//binding powers of the infix operators as (left, right), higher ones bind tighter and a right
+//power above the left one makes the operator left associative
+const INFIX_POWERS: &[(Operator, u8, u8)] = &[
+    (Operator::Or, 1, 2),
+    (Operator::And, 3, 4),
+    (Operator::EqEq, 5, 6),
+    (Operator::NotEq, 5, 6),
+    (Operator::Less, 7, 8),
+    (Operator::LessEq, 7, 8),
+    (Operator::Greater, 7, 8),
+    (Operator::GreaterEq, 7, 8),
+    (Operator::Pipe, 9, 10),
+    (Operator::Caret, 11, 12),
+    (Operator::Ampersand, 13, 14),
+    (Operator::ShiftLeft, 15, 16),
+    (Operator::ShiftRight, 15, 16),
+    (Operator::Plus, 17, 18),
+    (Operator::Minus, 17, 18),
+    (Operator::Star, 19, 20),
+    (Operator::Bar, 19, 20),
+    (Operator::Percent, 19, 20),
+];
+//casts bind tighter than any infix operator, and unary operators tighter than casts
+const CAST_POWER: u8 = 21;
+const PREFIX_POWER: u8 = 23;
 #[derive(Debug, Clone)]
 pub struct ParseStep {
     line: usize,
@@ -13,17 +39,22 @@ pub struct ParseStep {
 pub struct Parser {
     tokens: std::collections::VecDeque<Token>,
     pub backtrace: std::collections::VecDeque<ParseStep>,
+    //inner doc comments at the top of the source, documenting the program itself
+    pub docs: Vec<String>,
+    //span of the last token taken from the queue, where the node being parsed ends so far
+    last: Span,
 }
 
 #[derive(Debug, Clone)]
 pub enum ParsingError {
     InQueueParsing,
     EndedTokens,
-    UnexpectedToken(Token), //got a token and dont know how to handle it
+    UnexpectedToken(Token),    //got a token and dont know how to handle it
+    DanglingDocComment(Token), //the first of some doc comments not followed by a declaration
     WrongToken {
         expected: TokenKind,
         received: TokenKind,
-        token: Token,
+        token: Box<Token>,
     }, //got a token that shouldnt be here, such as let 5 = 5;
     ExpectedBlock(Box<Expression>),
 }
@@ -33,6 +64,8 @@ impl Parser {
         Self {
             tokens: std::collections::VecDeque::new(),
             backtrace: std::collections::VecDeque::new(),
+            docs: Vec::new(),
+            last: Span::default(),
         }
     }
     fn create_step<T>(&mut self, line: u32, column: u32, token: Token, fname: T)
@@ -49,15 +82,24 @@ impl Parser {
     fn peek(&self) -> Option<&Token> {
         self.tokens.get(0)
     }
+    fn pop(&mut self) -> Option<Token> {
+        let token = self.tokens.pop_front()?;
+        self.last = token.span();
+        Some(token)
+    }
+    //a node spanning from start up to the last token taken
+    fn node(&self, kind: ExpressionKind, start: Span) -> Expression {
+        Expression::new(kind, start.to(self.last))
+    }
     fn eat(&mut self) -> Result<Token, ParsingError> {
-        if let Some(t) = self.tokens.pop_front() {
+        if let Some(t) = self.pop() {
             Ok(t)
         } else {
             Err(ParsingError::EndedTokens)
         }
     }
     fn expect_exact(&mut self, tk: TokenKind) -> Result<Token, ParsingError> {
-        let Some(token) = self.tokens.pop_front() else {
+        let Some(token) = self.pop() else {
             return Err(ParsingError::EndedTokens);
         };
         self.create_step(line!(), column!(), token.clone(), "expect_exact");
@@ -67,12 +109,12 @@ impl Parser {
             Err(ParsingError::WrongToken {
                 expected: tk,
                 received: token.kind.clone(),
-                token,
+                token: Box::new(token),
             })
         }
     }
     fn expect(&mut self, tk: TokenKind) -> Result<Token, ParsingError> {
-        let Some(token) = self.tokens.pop_front() else {
+        let Some(token) = self.pop() else {
             return Err(ParsingError::EndedTokens);
         };
         self.create_step(line!(), column!(), token.clone(), "expect");
@@ -82,7 +124,7 @@ impl Parser {
             Err(ParsingError::WrongToken {
                 expected: tk,
                 received: token.kind.clone(),
-                token,
+                token: Box::new(token),
             })
         }
     }
@@ -94,14 +136,15 @@ impl Parser {
             return Err(ParsingError::InQueueParsing);
         }
         self.tokens.append(tokens);
+        self.docs = self.parse_docs(true);
         let mut expressions = Vec::new();
         while let Some(Token { kind, .. }) = self.peek() {
             match kind {
                 TokenKind::Eof => break,
                 _ => {
                     let expr = self.parse()?;
-                    if let Expression::FuncDecl { block, .. } = &expr {
-                        if let Expression::Block(_) = **block {
+                    if let ExpressionKind::FuncDecl { block, .. } = &expr.kind {
+                        if let ExpressionKind::Block(_) = block.kind {
                             expressions.push(expr);
                             continue;
                         } else {
@@ -116,21 +159,76 @@ impl Parser {
             }
             self.backtrace.clear();
         }
-        Ok(Expression::Program(expressions))
+        Ok(Expression::new(
+            ExpressionKind::Program(expressions),
+            Span::new(0, self.last.end),
+        ))
+    }
+    //consumes the outer or inner doc comments in front of the next token
+    fn parse_docs(&mut self, inner: bool) -> Vec<String> {
+        let mut docs = Vec::new();
+        while let Some(token) = self.peek() {
+            match (&token.kind, inner) {
+                (TokenKind::DocComment(doc), false) | (TokenKind::InnerDocComment(doc), true) => {
+                    docs.push(doc.clone())
+                }
+                _ => break,
+            }
+            //not part of any node, so it is left out of the spans
+            self.tokens.pop_front();
+        }
+        docs
     }
     fn parse(&mut self) -> Result<Expression, ParsingError> {
-        let tk = self.eat()?;
+        let doc_token = self.peek().cloned();
+        let docs = self.parse_docs(false);
+        let tk = self.eat();
+        //docs with nothing after them, or with something other than a declaration
+        if !docs.is_empty()
+            && !matches!(
+                tk,
+                Ok(Token {
+                    kind: TokenKind::Let | TokenKind::Func,
+                    ..
+                })
+            )
+        {
+            return Err(ParsingError::DanglingDocComment(doc_token.unwrap()));
+        }
+        let tk = tk?;
         self.create_step(line!(), column!(), tk.clone(), "parse");
         match tk.kind {
-            TokenKind::Let => self.parse_let_expr(),
-            TokenKind::Func => self.parse_func(tk),
-            TokenKind::IntLit(_) | TokenKind::FloatLit(_) | TokenKind::Identifier(_) => {
-                self.parse_secondary(tk)
+            TokenKind::Let => self.parse_let_expr(tk, docs),
+            TokenKind::Identifier(_)
+                if matches!(
+                    self.peek().map(|t| &t.kind),
+                    Some(TokenKind::Operator(op)) if op.is_assignment()
+                ) =>
+            {
+                self.parse_assign(tk)
+            }
+            TokenKind::Func => self.parse_func(tk, docs),
+            TokenKind::If => self.parse_if(tk),
+            TokenKind::While => self.parse_while(tk),
+            TokenKind::For => self.parse_for(tk),
+            TokenKind::Break => Ok(Expression::new(ExpressionKind::Break, tk.span())),
+            TokenKind::Continue => Ok(Expression::new(ExpressionKind::Continue, tk.span())),
+            TokenKind::Return => self.parse_return(tk),
+            TokenKind::IntLit(_)
+            | TokenKind::FloatLit(_)
+            | TokenKind::BoolLit(_)
+            | TokenKind::StrLit(_)
+            | TokenKind::CharLit(_)
+            | TokenKind::Identifier(_)
+            | TokenKind::OpenParen
+            | TokenKind::Operator(Operator::Minus | Operator::Not | Operator::Tilde) => {
+                self.parse_expr(tk, 0)
             }
             _ => self.parse_primary(tk),
         }
     }
-    fn parse_func(&mut self, tk: Token) -> Result<Expression, ParsingError> {
+    fn parse_func(&mut self, tk: Token, mut docs: Vec<String>) -> Result<Expression, ParsingError> {
+        let start = tk.span();
         self.create_step(line!(), column!(), tk, "parse_func");
         let TokenKind::Identifier(fname) = self.expect(TokenKind::Identifier(format!("")))?.kind
         else {
@@ -143,10 +241,7 @@ impl Parser {
                 self.eat()?;
                 break;
             }
-            let TokenKind::Identifier(name) = self.expect(TokenKind::Identifier(format!("")))?.kind
-            else {
-                unreachable!();
-            };
+            let name = self.parse_binding()?;
             self.expect(TokenKind::Colon)?;
             let TokenKind::Identifier(ptype) =
                 self.expect(TokenKind::Identifier(format!("")))?.kind
@@ -190,32 +285,114 @@ impl Parser {
             None => return Err(ParsingError::EndedTokens),
             Some(_) => return Err(ParsingError::UnexpectedToken(current.unwrap().clone())),
         };
-        let block = self.parse()?;
-        if expect_block {
-            if let Expression::Block(_) = block {
-                Ok(Expression::FuncDecl {
-                    identifier: fname,
-                    params,
-                    rtype,
-                    block: Box::new(block),
-                })
+        let block = match self.peek().map(|t| &t.kind) {
+            Some(TokenKind::OpenBrace) if expect_block => {
+                self.eat()?;
+                //inner doc comments at the start of the body document the function too
+                docs.extend(self.parse_docs(true));
+                self.parse_block()?
+            }
+            _ => self.parse()?,
+        };
+        if expect_block && !matches!(block.kind, ExpressionKind::Block(_)) {
+            return Err(ParsingError::ExpectedBlock(Box::new(block)));
+        }
+        let func = ExpressionKind::FuncDecl {
+            docs,
+            identifier: fname,
+            params,
+            rtype,
+            block: Box::new(block),
+        };
+        Ok(self.node(func, start))
+    }
+    fn parse_if(&mut self, tk: Token) -> Result<Expression, ParsingError> {
+        let start = tk.span();
+        self.create_step(line!(), column!(), tk, "parse_if");
+        let condition = self.parse()?;
+        self.expect(TokenKind::OpenBrace)?;
+        let then_block = self.parse_block()?;
+        let else_block = if let Some(TokenKind::Else) = self.peek().map(|t| &t.kind) {
+            self.eat()?;
+            if let Some(TokenKind::If) = self.peek().map(|t| &t.kind) {
+                let tk = self.eat()?;
+                Some(Box::new(self.parse_if(tk)?))
             } else {
-                Err(ParsingError::ExpectedBlock(Box::new(block)))
+                self.expect(TokenKind::OpenBrace)?;
+                Some(Box::new(self.parse_block()?))
             }
         } else {
-            Ok(Expression::FuncDecl {
-                identifier: fname,
-                params,
-                rtype,
-                block: Box::new(block),
-            })
-        }
+            None
+        };
+        let if_expr = ExpressionKind::If {
+            condition: Box::new(condition),
+            then_block: Box::new(then_block),
+            else_block,
+        };
+        Ok(self.node(if_expr, start))
     }
-    fn parse_secondary(&mut self, tk: Token) -> Result<Expression, ParsingError> {
-        self.create_step(line!(), column!(), tk.clone(), "parse_secondary");
-        self.parse_additive(tk)
+    fn parse_while(&mut self, tk: Token) -> Result<Expression, ParsingError> {
+        let start = tk.span();
+        self.create_step(line!(), column!(), tk, "parse_while");
+        let condition = self.parse()?;
+        self.expect(TokenKind::OpenBrace)?;
+        let block = self.parse_block()?;
+        let while_expr = ExpressionKind::While {
+            condition: Box::new(condition),
+            block: Box::new(block),
+        };
+        Ok(self.node(while_expr, start))
     }
+    fn parse_for(&mut self, tk: Token) -> Result<Expression, ParsingError> {
+        let start = tk.span();
+        self.create_step(line!(), column!(), tk, "parse_for");
+        let varname = self.parse_binding()?;
+        self.expect(TokenKind::In)?;
+        let range_start = self.parse()?;
+        let inclusive = match self.eat()? {
+            Token {
+                kind: TokenKind::DotDot,
+                ..
+            } => false,
+            Token {
+                kind: TokenKind::DotDotEq,
+                ..
+            } => true,
+            token => {
+                return Err(ParsingError::WrongToken {
+                    expected: TokenKind::DotDot,
+                    received: token.kind.clone(),
+                    token: Box::new(token),
+                })
+            }
+        };
+        let end = self.parse()?;
+        self.expect(TokenKind::OpenBrace)?;
+        let block = self.parse_block()?;
+        let for_expr = ExpressionKind::For {
+            varname,
+            start: Box::new(range_start),
+            end: Box::new(end),
+            inclusive,
+            block: Box::new(block),
+        };
+        Ok(self.node(for_expr, start))
+    }
+    fn parse_return(&mut self, tk: Token) -> Result<Expression, ParsingError> {
+        let start = tk.span();
+        self.create_step(line!(), column!(), tk, "parse_return");
+        let value = if let Some(TokenKind::SemiColon | TokenKind::CloseBrace) =
+            self.peek().map(|t| &t.kind)
+        {
+            None
+        } else {
+            Some(Box::new(self.parse()?))
+        };
+        Ok(self.node(ExpressionKind::Return(value), start))
+    }
+    //the opening brace was already taken, so the block starts at the last token
     fn parse_block(&mut self) -> Result<Expression, ParsingError> {
+        let start = self.last;
         self.create_step(
             line!(),
             column!(),
@@ -223,8 +400,20 @@ impl Parser {
             "parse_block",
         );
         let mut exprs = Vec::new();
+        if let Some(TokenKind::CloseBrace) = self.peek().map(|t| &t.kind) {
+            self.eat()?;
+            return Ok(self.node(ExpressionKind::Block(exprs), start));
+        }
         loop {
             exprs.push(self.parse()?);
+            if exprs.last().is_some_and(Expression::ends_with_block)
+                && !matches!(
+                    self.peek().map(|t| &t.kind),
+                    Some(TokenKind::SemiColon | TokenKind::CloseBrace)
+                )
+            {
+                continue;
+            }
             let err = self.expect(TokenKind::SemiColon);
             if let Err(ParsingError::WrongToken { ref token, .. }) = err {
                 if token.kind == TokenKind::CloseBrace {
@@ -238,86 +427,180 @@ impl Parser {
                 break;
             }
         }
-        Ok(Expression::Block(exprs))
+        Ok(self.node(ExpressionKind::Block(exprs), start))
     }
-    fn parse_additive(&mut self, tk: Token) -> Result<Expression, ParsingError> {
-        self.create_step(line!(), column!(), tk.clone(), "parse_additive");
-        let mut left = self.parse_multiplicative(tk)?;
+    //pratt parser over the binding powers of the operators, only the ones that bind tighter than
+    //min_power are taken as part of this expression
+    fn parse_expr(&mut self, tk: Token, min_power: u8) -> Result<Expression, ParsingError> {
+        self.create_step(line!(), column!(), tk.clone(), "parse_expr");
+        let mut left = self.parse_prefix(tk)?;
         loop {
-            let Some(current) = self.peek() else {
-                break;
-            };
-            if let TokenKind::Operator(operator @ (Operator::Plus | Operator::Minus)) = current.kind
-            {
-                self.eat()?;
-                left = Expression::BinExpr {
-                    lhs: Box::new(left),
-                    rhs: Box::new({
-                        let tk = self.eat()?;
-                        self.parse_multiplicative(tk)?
-                    }),
-                    op: operator,
+            match self.peek().map(|t| &t.kind) {
+                Some(TokenKind::As) if CAST_POWER > min_power => {
+                    self.eat()?;
+                    let TokenKind::Identifier(to) =
+                        self.expect(TokenKind::Identifier(format!("")))?.kind
+                    else {
+                        unreachable!();
+                    };
+                    let start = left.span;
+                    let cast = ExpressionKind::Cast {
+                        expr: Box::new(left),
+                        to,
+                    };
+                    left = self.node(cast, start);
                 }
-            } else {
-                break;
+                Some(TokenKind::Operator(operator)) => {
+                    let operator = *operator;
+                    let Some((left_power, right_power)) = Self::infix_power(operator) else {
+                        break;
+                    };
+                    if left_power <= min_power {
+                        break;
+                    }
+                    self.eat()?;
+                    let start = left.span;
+                    let binexpr = ExpressionKind::BinExpr {
+                        lhs: Box::new(left),
+                        rhs: Box::new({
+                            let tk = self.eat()?;
+                            self.parse_expr(tk, right_power)?
+                        }),
+                        op: operator,
+                    };
+                    left = self.node(binexpr, start);
+                }
+                _ => break,
             }
         }
         Ok(left)
     }
-    fn parse_multiplicative(&mut self, tk: Token) -> Result<Expression, ParsingError> {
-        self.create_step(line!(), column!(), tk.clone(), "parse_multiplicative");
-        let mut left = self.parse_primary(tk)?;
+    fn parse_prefix(&mut self, tk: Token) -> Result<Expression, ParsingError> {
+        self.create_step(line!(), column!(), tk.clone(), "parse_prefix");
+        let start = tk.span();
+        let TokenKind::Operator(operator @ (Operator::Minus | Operator::Not | Operator::Tilde)) =
+            tk.kind
+        else {
+            return self.parse_primary(tk);
+        };
+        let operand = Box::new({
+            let tk = self.eat()?;
+            self.parse_expr(tk, PREFIX_POWER)?
+        });
+        let prefix = match operator {
+            Operator::Minus => ExpressionKind::Negative(operand),
+            Operator::Not => ExpressionKind::Not(operand),
+            _ => ExpressionKind::BitNot(operand),
+        };
+        Ok(self.node(prefix, start))
+    }
+    fn infix_power(operator: Operator) -> Option<(u8, u8)> {
+        INFIX_POWERS
+            .iter()
+            .find(|(op, ..)| *op == operator)
+            .map(|(_, left, right)| (*left, *right))
+    }
+    fn parse_call(&mut self, identifier: String, start: Span) -> Result<Expression, ParsingError> {
+        let tk = self.expect(TokenKind::OpenParen)?;
+        self.create_step(line!(), column!(), tk, "parse_call");
+        let mut args = Vec::new();
         loop {
-            let Some(current) = self.peek() else {
+            if let Some(TokenKind::CloseParen) = self.peek().map(|t| &t.kind) {
+                self.eat()?;
                 break;
-            };
-            if let TokenKind::Operator(operator @ (Operator::Star | Operator::Bar)) = current.kind {
+            }
+            args.push(self.parse()?);
+            if let Some(TokenKind::CloseParen) = self.peek().map(|t| &t.kind) {
                 self.eat()?;
-                left = Expression::BinExpr {
-                    lhs: Box::new(left),
-                    rhs: Box::new({
-                        let tk = self.eat()?;
-                        self.parse_primary(tk)?
-                    }),
-                    op: operator,
-                }
-            } else {
                 break;
+            } else {
+                self.expect(TokenKind::Comma)?;
             }
         }
-        Ok(left)
+        Ok(self.node(ExpressionKind::Call { identifier, args }, start))
     }
-    fn parse_let_expr(&mut self) -> Result<Expression, ParsingError> {
-        self.create_step(
-            line!(),
-            column!(),
-            self.peek().unwrap().clone(),
-            "parse_let_expr",
-        );
-        let TokenKind::Identifier(varname) = self.expect(TokenKind::Identifier(format!("")))?.kind
-        else {
-            unreachable!()
+    fn parse_let_expr(&mut self, tk: Token, docs: Vec<String>) -> Result<Expression, ParsingError> {
+        let start = tk.span();
+        self.create_step(line!(), column!(), tk, "parse_let_expr");
+        let kind = if let Some(TokenKind::Mut) = self.peek().map(|t| &t.kind) {
+            self.eat()?;
+            LetDeclKind::Mutable
+        } else {
+            LetDeclKind::Normal
         };
+        let varname = self.parse_binding()?;
         self.expect_exact(TokenKind::Operator(Operator::Eq))?;
-        Ok(Expression::LetDecl {
-            kind: LetDeclKind::Normal,
+        let let_decl = ExpressionKind::LetDecl {
+            docs,
+            kind,
             varname,
             expr: Box::new(self.parse()?),
-        })
+        };
+        Ok(self.node(let_decl, start))
+    }
+    //a name to bind a value to, or _ to discard it
+    fn parse_binding(&mut self) -> Result<String, ParsingError> {
+        if let Some(TokenKind::Underscore) = self.peek().map(|t| &t.kind) {
+            self.eat()?;
+            return Ok(String::from("_"));
+        }
+        let TokenKind::Identifier(name) = self.expect(TokenKind::Identifier(format!("")))?.kind
+        else {
+            unreachable!()
+        };
+        Ok(name)
+    }
+    fn parse_assign(&mut self, tk: Token) -> Result<Expression, ParsingError> {
+        self.create_step(line!(), column!(), tk.clone(), "parse_assign");
+        let start = tk.span();
+        let TokenKind::Identifier(varname) = tk.kind else {
+            unreachable!()
+        };
+        let TokenKind::Operator(operator) = self.eat()?.kind else {
+            unreachable!()
+        };
+        let expr = self.parse()?;
+        //a compound assignment like x += 1 is the same as x = x + 1
+        let expr = if let Some(op) = operator.assignment_base() {
+            let lhs = Expression::new(ExpressionKind::Identifier(varname.clone()), start);
+            let binexpr = ExpressionKind::BinExpr {
+                lhs: Box::new(lhs),
+                rhs: Box::new(expr),
+                op,
+            };
+            self.node(binexpr, start)
+        } else {
+            expr
+        };
+        let assign = ExpressionKind::Assign {
+            varname,
+            expr: Box::new(expr),
+        };
+        Ok(self.node(assign, start))
     }
     fn parse_primary(&mut self, token: Token) -> Result<Expression, ParsingError> {
         self.create_step(line!(), column!(), token.clone(), "parse_primary");
+        let span = token.span();
+        let leaf = |kind| Ok(Expression::new(kind, span));
         match token.kind {
-            TokenKind::Identifier(vname) => Ok(Expression::Identifier(vname)),
-            TokenKind::IntLit(lit) => Ok(Expression::IntLit(lit)),
-            TokenKind::FloatLit(f) => Ok(Expression::FloatLit(f)),
-            TokenKind::Operator(Operator::Minus) => {
-                Ok(Expression::Negative(Box::new(self.parse()?)))
+            TokenKind::Identifier(vname) => {
+                if let Some(TokenKind::OpenParen) = self.peek().map(|t| &t.kind) {
+                    self.parse_call(vname, span)
+                } else {
+                    leaf(ExpressionKind::Identifier(vname))
+                }
             }
+            TokenKind::IntLit(lit) => leaf(ExpressionKind::IntLit(lit)),
+            TokenKind::FloatLit(f) => leaf(ExpressionKind::FloatLit(f)),
+            TokenKind::BoolLit(b) => leaf(ExpressionKind::BoolLit(b)),
+            TokenKind::StrLit(s) => leaf(ExpressionKind::StrLit(s)),
+            TokenKind::CharLit(c) => leaf(ExpressionKind::CharLit(c)),
             TokenKind::OpenParen => {
-                let r = Ok(self.parse()?);
+                let mut expr = self.parse()?;
                 self.expect(TokenKind::CloseParen)?;
-                r
+                //the parentheses are part of the expression
+                expr.span = span.to(self.last);
+                Ok(expr)
             }
             TokenKind::OpenBrace => Ok(self.parse_block()?),
             _ => Err(ParsingError::UnexpectedToken(token)),
@@ -334,3 +617,123 @@ impl std::fmt::Display for ParseStep {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    fn parse(source: &str) -> Result<Expression, ParsingError> {
+        let mut tokens = Tokenizer::new(source.to_string()).gen().unwrap();
+        Parser::new().parse_tokens(&mut tokens)
+    }
+
+    //the single expression statement in the source
+    fn expr(source: &str) -> Expression {
+        let ExpressionKind::Program(mut exprs) = parse(&format!("{source};")).unwrap().kind else {
+            unreachable!();
+        };
+        exprs.remove(0)
+    }
+
+    //shows how an expression was grouped, with parentheses around every operation
+    fn grouping(expr: &Expression) -> String {
+        match &expr.kind {
+            ExpressionKind::BinExpr { lhs, rhs, op } => {
+                format!("({} {op:?} {})", grouping(lhs), grouping(rhs))
+            }
+            ExpressionKind::Cast { expr, to } => format!("({} as {to})", grouping(expr)),
+            ExpressionKind::Negative(expr) => format!("(-{})", grouping(expr)),
+            ExpressionKind::Not(expr) => format!("(!{})", grouping(expr)),
+            ExpressionKind::BitNot(expr) => format!("(~{})", grouping(expr)),
+            ExpressionKind::Identifier(name) => name.clone(),
+            ExpressionKind::IntLit(lit) => lit.clone(),
+            kind => panic!("unexpected {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn operator_precedence() {
+        let cases = [
+            ("a + b * c", "(a Plus (b Star c))"),
+            ("a * b + c", "((a Star b) Plus c)"),
+            ("a || b && c", "(a Or (b And c))"),
+            ("a == b < c", "(a EqEq (b Less c))"),
+            ("a | b ^ c & d", "(a Pipe (b Caret (c Ampersand d)))"),
+            ("a & b << 1", "(a Ampersand (b ShiftLeft 1))"),
+            ("a << 1 + b", "(a ShiftLeft (1 Plus b))"),
+            ("a < b && b < c", "((a Less b) And (b Less c))"),
+            ("(a + b) * c", "((a Plus b) Star c)"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(grouping(&expr(source)), expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn prefix_operators_and_casts() {
+        let cases = [
+            ("-a * b", "((-a) Star b)"),
+            ("-a as int64", "((-a) as int64)"),
+            ("a + b as int64", "(a Plus (b as int64))"),
+            ("a as int8 as int64", "((a as int8) as int64)"),
+            ("!a == b", "((!a) EqEq b)"),
+            ("~a & b", "((~a) Ampersand b)"),
+            ("--a", "(-(-a))"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(grouping(&expr(source)), expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn left_associativity() {
+        let cases = [
+            ("a - b - c", "((a Minus b) Minus c)"),
+            ("a / b * c", "((a Bar b) Star c)"),
+            ("a << b >> c", "((a ShiftLeft b) ShiftRight c)"),
+            ("a || b || c", "((a Or b) Or c)"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(grouping(&expr(source)), expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn node_spans() {
+        let source = "func ñ(x: int32): int32 {\n    let y = (x + 1) * 2;\n    y\n}";
+        let program = parse(source).unwrap();
+        let slice = |expr: &Expression| &source[expr.span.start..expr.span.end];
+        let ExpressionKind::Program(funcs) = &program.kind else {
+            unreachable!();
+        };
+        assert_eq!(slice(&funcs[0]), source);
+        let ExpressionKind::FuncDecl { block, .. } = &funcs[0].kind else {
+            unreachable!();
+        };
+        let ExpressionKind::Block(body) = &block.kind else {
+            unreachable!();
+        };
+        assert_eq!(slice(&body[0]), "let y = (x + 1) * 2");
+        assert_eq!(slice(&body[1]), "y");
+        let ExpressionKind::LetDecl { expr, .. } = &body[0].kind else {
+            unreachable!();
+        };
+        let ExpressionKind::BinExpr { lhs, rhs, .. } = &expr.kind else {
+            unreachable!();
+        };
+        assert_eq!(slice(expr), "(x + 1) * 2");
+        assert_eq!(slice(lhs), "(x + 1)");
+        assert_eq!(slice(rhs), "2");
+    }
+
+    #[test]
+    fn dangling_doc_comments() {
+        for source in ["let x = 1;\n/// trailing", "/// a\n/// b", "/// docs\n5;"] {
+            let Err(ParsingError::DanglingDocComment(token)) = parse(source) else {
+                panic!("{source:?} should not parse");
+            };
+            assert!(matches!(token.kind, TokenKind::DocComment(_)));
+        }
+    }
+}
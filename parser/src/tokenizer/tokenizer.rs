@@ -1,12 +1,20 @@
 use std::collections::VecDeque;
 
-use super::{Cursor, Operator, Token, TokenKind};
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+use super::{Cursor, Operator, Span, Token, TokenKind};
 
 #[derive(Debug, Clone)]
 pub enum TokenizationErrorKind {
     FoundUnexpectedEof,
     UnexpectedChar(char),
     InvalidDigit(String),
+    UnterminatedComment,
+    UnterminatedString,
+    InvalidEscape(String),
+    EmptyChar,
+    MultipleChars,
+    UnterminatedChar,
 }
 
 #[derive(Debug, Clone)]
@@ -22,39 +30,90 @@ impl TokenizationError {
     pub fn invalid_digit(buf: String, cursor: &Cursor) -> Self {
         Self::new(TokenizationErrorKind::InvalidDigit(buf), cursor)
     }
+    //reported where the comment was opened, as the cursor has already reached the end of the file
+    pub fn unterminated_comment(line: usize, column: usize) -> Self {
+        Self::at(TokenizationErrorKind::UnterminatedComment, line, column)
+    }
+    pub fn unterminated_string(line: usize, column: usize) -> Self {
+        Self::at(TokenizationErrorKind::UnterminatedString, line, column)
+    }
+    pub fn invalid_escape(escape: String, cursor: &Cursor) -> Self {
+        Self::new(TokenizationErrorKind::InvalidEscape(escape), cursor)
+    }
     pub fn unexpected_eof(cursor: &Cursor) -> Self {
         Self::new(TokenizationErrorKind::FoundUnexpectedEof, cursor)
     }
     pub fn new(kind: TokenizationErrorKind, cursor: &Cursor) -> Self {
-        Self {
-            kind,
-            line: cursor.line(),
-            column: cursor.column(),
-        }
+        Self::at(kind, cursor.line(), cursor.column())
+    }
+    pub fn at(kind: TokenizationErrorKind, line: usize, column: usize) -> Self {
+        Self { kind, line, column }
+    }
+}
+impl std::fmt::Display for TokenizationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} at <{}:{}>", self.kind, self.line, self.column)
     }
 }
+//longer symbols must come before their prefixes
+const OPERATORS: &[(&str, Operator)] = &[
+    ("<<=", Operator::ShiftLeftEq),
+    (">>=", Operator::ShiftRightEq),
+    ("==", Operator::EqEq),
+    ("!=", Operator::NotEq),
+    ("<=", Operator::LessEq),
+    (">=", Operator::GreaterEq),
+    ("&&", Operator::And),
+    ("||", Operator::Or),
+    ("+=", Operator::PlusEq),
+    ("-=", Operator::MinusEq),
+    ("*=", Operator::StarEq),
+    ("/=", Operator::BarEq),
+    ("%=", Operator::PercentEq),
+    ("&=", Operator::AmpersandEq),
+    ("|=", Operator::PipeEq),
+    ("^=", Operator::CaretEq),
+    ("<<", Operator::ShiftLeft),
+    (">>", Operator::ShiftRight),
+    ("=", Operator::Eq),
+    ("+", Operator::Plus),
+    ("-", Operator::Minus),
+    ("*", Operator::Star),
+    ("/", Operator::Bar),
+    ("%", Operator::Percent),
+    ("&", Operator::Ampersand),
+    ("|", Operator::Pipe),
+    ("^", Operator::Caret),
+    ("~", Operator::Tilde),
+    ("<", Operator::Less),
+    (">", Operator::Greater),
+    ("!", Operator::Not),
+];
+const INT_SUFFIXES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64", "usize",
+];
 pub struct Tokenizer {
     content: String,
 }
 
 impl Tokenizer {
-    pub fn get_char<'a>(
-        cursor: &'a mut Cursor,
-        chars: &'a Vec<char>,
-        advance: bool,
-    ) -> Result<&'a char, TokenizationError> {
-        let tk = chars
-            .get(cursor.index())
-            .ok_or(TokenizationError::unexpected_eof(cursor));
-        if advance {
-            cursor.advance();
-        }
-        tk
-    }
     pub fn check_for_reserved(buf: String, cursor: &Cursor) -> Token {
         match &*buf {
             "let" => Token::let_token(cursor),
+            "mut" => Token::new(TokenKind::Mut, cursor),
             "func" => Token::func(cursor),
+            "if" => Token::new(TokenKind::If, cursor),
+            "else" => Token::new(TokenKind::Else, cursor),
+            "while" => Token::new(TokenKind::While, cursor),
+            "for" => Token::new(TokenKind::For, cursor),
+            "in" => Token::new(TokenKind::In, cursor),
+            "break" => Token::new(TokenKind::Break, cursor),
+            "continue" => Token::new(TokenKind::Continue, cursor),
+            "return" => Token::new(TokenKind::Return, cursor),
+            "as" => Token::new(TokenKind::As, cursor),
+            "true" => Token::new(TokenKind::BoolLit(true), cursor),
+            "false" => Token::new(TokenKind::BoolLit(false), cursor),
+            "_" => Token::new(TokenKind::Underscore, cursor),
             _ => return Token::identifier(buf, cursor),
         }
     }
@@ -64,16 +123,26 @@ impl Tokenizer {
     pub fn gen(&self) -> Result<VecDeque<Token>, TokenizationError> {
         let mut vec = VecDeque::new();
         let chars: Vec<char> = self.content.chars().collect();
+        //byte offset of every char, followed by the length of the source
+        let offsets: Vec<usize> = self
+            .content
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([self.content.len()])
+            .collect();
         let mut cursor = Cursor::new();
         while let Some(chr) = chars.get(cursor.index()) {
-            vec.push_back(match chr {
+            let start = offsets[cursor.index()];
+            let token = match chr {
                 ';' => Token::new(TokenKind::SemiColon, &cursor),
                 ':' => Token::new(TokenKind::Colon, &cursor),
-                '=' => Token::new(TokenKind::Operator(Operator::Eq), &cursor),
-                '+' => Token::new(TokenKind::Operator(Operator::Plus), &cursor),
-                '-' => Token::new(TokenKind::Operator(Operator::Minus), &cursor),
-                '*' => Token::new(TokenKind::Operator(Operator::Star), &cursor),
-                '/' => Token::new(TokenKind::Operator(Operator::Bar), &cursor),
+                ',' => Token::new(TokenKind::Comma, &cursor),
+                '.' => Self::get_range(&mut cursor, &chars)?,
+                '"' => Self::get_string_lit(&mut cursor, &chars)?,
+                '\'' => Self::get_char_lit(&mut cursor, &chars)?,
+                'r' if chars.get(cursor.index() + 1) == Some(&'"') => {
+                    Self::get_string_lit(&mut cursor, &chars)?
+                }
                 '(' => Token::new(TokenKind::OpenParen, &cursor),
                 ')' => Token::new(TokenKind::CloseParen, &cursor),
                 '{' => Token::new(TokenKind::OpenBrace, &cursor),
@@ -82,70 +151,505 @@ impl Tokenizer {
                     cursor.advance_line();
                     continue;
                 }
+                '/' if matches!(chars.get(cursor.index() + 1), Some('/' | '*')) => {
+                    match Self::get_doc_comment(&mut cursor, &chars) {
+                        Some(token) => token,
+                        None => {
+                            Self::skip_comment(&mut cursor, &chars)?;
+                            continue;
+                        }
+                    }
+                }
                 _ => {
                     if chr.is_whitespace() {
                         cursor.advance();
                         continue;
                     } else if chr.is_ascii_digit() {
                         Self::get_digit_lit(&mut cursor, &chars)?
-                    } else if chr.is_alphabetic() {
-                        Self::get_identifier(&mut cursor, &chars)?
+                    } else if *chr == '_' || is_xid_start(*chr) {
+                        Self::get_identifier(&mut cursor, &chars)
+                    } else if let Some(token) = Self::get_operator(&mut cursor, &chars) {
+                        token
                     } else {
                         return Err(TokenizationError::unexpected_char(*chr, &cursor));
                     }
                 }
-            });
+            };
+            //tokens leave the cursor on their last char
+            let end = offsets[cursor.index() + 1];
+            vec.push_back(token.with_span(Span::new(start, end)));
             cursor.advance();
         }
         Ok(vec)
     }
-    pub fn get_identifier(
-        cursor: &mut Cursor,
-        chars: &Vec<char>,
-    ) -> Result<Token, TokenizationError> {
+    //raw strings, starting with r, take backslashes as they are
+    pub fn get_string_lit(cursor: &mut Cursor, chars: &[char]) -> Result<Token, TokenizationError> {
+        let (line, column) = (cursor.line(), cursor.column());
+        let raw = chars[cursor.index()] == 'r';
+        if raw {
+            cursor.advance();
+        }
+        cursor.advance();
         let mut buf = String::new();
         loop {
-            let chr = Self::get_char(cursor, chars, true)?;
-            if chr.is_alphanumeric() {
-                buf.push(*chr);
-            } else {
-                cursor.backward();
-                break;
+            match chars.get(cursor.index()) {
+                None => return Err(TokenizationError::unterminated_string(line, column)),
+                Some('"') => break,
+                Some('\\') if !raw => buf.push(Self::get_escape(cursor, chars)?),
+                Some('\n') => {
+                    buf.push('\n');
+                    cursor.advance_line();
+                }
+                Some(chr) => {
+                    buf.push(*chr);
+                    cursor.advance();
+                }
+            }
+        }
+        Ok(Token::new(TokenKind::StrLit(buf), cursor))
+    }
+    //errors are reported where the literal was opened
+    pub fn get_char_lit(cursor: &mut Cursor, chars: &[char]) -> Result<Token, TokenizationError> {
+        let (line, column) = (cursor.line(), cursor.column());
+        let error = |kind| Err(TokenizationError::at(kind, line, column));
+        cursor.advance();
+        let chr = match chars.get(cursor.index()) {
+            Some('\'') => return error(TokenizationErrorKind::EmptyChar),
+            None | Some('\n') => return error(TokenizationErrorKind::UnterminatedChar),
+            Some('\\') => Self::get_escape(cursor, chars)?,
+            Some(chr) => {
+                let chr = *chr;
+                cursor.advance();
+                chr
+            }
+        };
+        let rest = &chars[cursor.index()..];
+        if rest.first() == Some(&'\'') {
+            Ok(Token::new(TokenKind::CharLit(chr), cursor))
+        } else if rest.iter().take_while(|c| **c != '\n').any(|c| *c == '\'') {
+            error(TokenizationErrorKind::MultipleChars)
+        } else {
+            error(TokenizationErrorKind::UnterminatedChar)
+        }
+    }
+    //escape sequences starting at the backslash under the cursor, leaves the cursor after them
+    pub fn get_escape(cursor: &mut Cursor, chars: &[char]) -> Result<char, TokenizationError> {
+        let chr = match chars.get(cursor.index() + 1) {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('u') => return Self::get_unicode_escape(cursor, chars),
+            Some(other) => {
+                return Err(TokenizationError::invalid_escape(
+                    format!("\\{other}"),
+                    cursor,
+                ))
+            }
+            None => return Err(TokenizationError::unexpected_eof(cursor)),
+        };
+        cursor.advance();
+        cursor.advance();
+        Ok(chr)
+    }
+    //such as \u{1F600}, with up to six hex digits naming a unicode scalar value
+    fn get_unicode_escape(cursor: &mut Cursor, chars: &[char]) -> Result<char, TokenizationError> {
+        let rest = &chars[cursor.index()..];
+        let close = rest
+            .iter()
+            .take(10)
+            .position(|c| *c == '}')
+            .filter(|_| rest.get(2) == Some(&'{'));
+        let Some(close) = close else {
+            return Err(TokenizationError::invalid_escape("\\u".to_string(), cursor));
+        };
+        let escape: String = rest[..=close].iter().collect();
+        let digits: String = rest[3..close].iter().collect();
+        let chr = Some(digits)
+            .filter(|d| (1..=6).contains(&d.len()) && d.chars().all(|c| c.is_ascii_hexdigit()))
+            .and_then(|d| u32::from_str_radix(&d, 16).ok())
+            .and_then(char::from_u32);
+        let Some(chr) = chr else {
+            return Err(TokenizationError::invalid_escape(escape, cursor));
+        };
+        for _ in 0..=close {
+            cursor.advance();
+        }
+        Ok(chr)
+    }
+    //only /// and //! comments are kept, //// is a plain comment again
+    pub fn get_doc_comment(cursor: &mut Cursor, chars: &[char]) -> Option<Token> {
+        let rest = &chars[cursor.index()..];
+        let inner = match rest {
+            ['/', '/', '/', '/', ..] => return None,
+            ['/', '/', '/', ..] => false,
+            ['/', '/', '!', ..] => true,
+            _ => return None,
+        };
+        let text: String = rest[3..].iter().take_while(|c| **c != '\n').collect();
+        let length = text.chars().count();
+        let kind = if inner {
+            TokenKind::InnerDocComment(text)
+        } else {
+            TokenKind::DocComment(text)
+        };
+        let token = Token::new(kind, cursor);
+        //stops at the last char of the comment, gen advances past it
+        for _ in 0..length + 2 {
+            cursor.advance();
+        }
+        Some(token)
+    }
+    //leaves the cursor right after the comment, line comments stop before their newline
+    pub fn skip_comment(cursor: &mut Cursor, chars: &[char]) -> Result<(), TokenizationError> {
+        if chars.get(cursor.index() + 1) == Some(&'/') {
+            while chars.get(cursor.index()).is_some_and(|c| *c != '\n') {
+                cursor.advance();
+            }
+            return Ok(());
+        }
+        let (line, column) = (cursor.line(), cursor.column());
+        //block comments can be nested
+        let mut depth = 0;
+        loop {
+            match (chars.get(cursor.index()), chars.get(cursor.index() + 1)) {
+                (None, _) => return Err(TokenizationError::unterminated_comment(line, column)),
+                (Some('/'), Some('*')) => {
+                    depth += 1;
+                    cursor.advance();
+                    cursor.advance();
+                }
+                (Some('*'), Some('/')) => {
+                    depth -= 1;
+                    cursor.advance();
+                    cursor.advance();
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                (Some('\n'), _) => {
+                    cursor.advance_line();
+                }
+                _ => {
+                    cursor.advance();
+                }
             }
         }
+    }
+    pub fn get_operator(cursor: &mut Cursor, chars: &[char]) -> Option<Token> {
+        let rest = &chars[cursor.index()..];
+        let (symbol, operator) = OPERATORS.iter().find(|(symbol, _)| {
+            symbol.len() <= rest.len() && symbol.chars().zip(rest).all(|(a, b)| a == *b)
+        })?;
+        let token = Token::new(TokenKind::Operator(*operator), cursor);
+        for _ in 1..symbol.len() {
+            cursor.advance();
+        }
+        Some(token)
+    }
+    pub fn get_range(cursor: &mut Cursor, chars: &[char]) -> Result<Token, TokenizationError> {
+        if chars.get(cursor.index() + 1) != Some(&'.') {
+            return Err(TokenizationError::unexpected_char('.', cursor));
+        }
+        let token = if chars.get(cursor.index() + 2) == Some(&'=') {
+            let token = Token::new(TokenKind::DotDotEq, cursor);
+            cursor.advance();
+            token
+        } else {
+            Token::new(TokenKind::DotDot, cursor)
+        };
+        cursor.advance();
+        Ok(token)
+    }
+    //identifiers end at the first char that cannot continue them, or at the end of the source
+    pub fn get_identifier(cursor: &mut Cursor, chars: &[char]) -> Token {
+        let mut buf = String::new();
+        while let Some(chr) = chars.get(cursor.index()).filter(|c| is_xid_continue(**c)) {
+            buf.push(*chr);
+            cursor.advance();
+        }
         cursor.backward();
-        Ok(Self::check_for_reserved(buf, cursor))
+        Self::check_for_reserved(buf, cursor)
     }
     pub fn get_digit_lit(
         cursor: &mut Cursor,
         chars: &Vec<char>,
     ) -> Result<Token, TokenizationError> {
-        let mut buf = String::new();
-        let mut hasdot = false;
-        let mut rnormal = true;
-        loop {
-            let chr = Self::get_char(cursor, chars, true)?;
-            if chr.is_ascii_digit() || *chr == '.' {
-                if hasdot && rnormal && *chr == '.' {
-                    rnormal = false;
-                }
-                if *chr == '.' {
-                    hasdot = true;
-                }
-                buf.push(*chr);
-            } else {
-                cursor.backward();
-                break;
+        let radix = match (chars[cursor.index()], chars.get(cursor.index() + 1)) {
+            ('0', Some('x')) => 16,
+            ('0', Some('o')) => 8,
+            ('0', Some('b')) => 2,
+            _ => return Self::get_decimal_lit(cursor, chars),
+        };
+        let prefix: String = chars[cursor.index()..cursor.index() + 2].iter().collect();
+        cursor.advance();
+        cursor.advance();
+        let digits = Self::get_digits(cursor, chars, radix);
+        if let Some(chr) = chars.get(cursor.index()).filter(|c| c.is_ascii_digit()) {
+            //such as a 2 in a binary literal
+            return Err(TokenizationError::invalid_digit(
+                format!("{prefix}{digits}{chr}"),
+                cursor,
+            ));
+        }
+        let suffix = Self::get_suffix(cursor, chars);
+        cursor.backward();
+        if digits.is_empty() || !(suffix.is_empty() || INT_SUFFIXES.contains(&&*suffix)) {
+            return Err(TokenizationError::invalid_digit(
+                prefix + &digits + &suffix,
+                cursor,
+            ));
+        }
+        Ok(Token::int_lit(
+            Self::to_decimal(&digits, radix) + &suffix,
+            cursor,
+        ))
+    }
+    fn get_decimal_lit(cursor: &mut Cursor, chars: &[char]) -> Result<Token, TokenizationError> {
+        //a dot followed by another one is a range, not a decimal point
+        let at_dot = |cursor: &Cursor| {
+            chars.get(cursor.index()) == Some(&'.') && chars.get(cursor.index() + 1) != Some(&'.')
+        };
+        let mut buf = Self::get_digits(cursor, chars, 10);
+        let mut float = false;
+        if at_dot(cursor) {
+            float = true;
+            buf.push('.');
+            cursor.advance();
+            buf += &Self::get_digits(cursor, chars, 10);
+        }
+        if let Some(exp @ ('e' | 'E')) = chars.get(cursor.index()).copied() {
+            float = true;
+            buf.push(exp);
+            cursor.advance();
+            if let Some(sign @ ('+' | '-')) = chars.get(cursor.index()).copied() {
+                buf.push(sign);
+                cursor.advance();
+            }
+            let exponent = Self::get_digits(cursor, chars, 10);
+            if exponent.is_empty() {
+                return Err(TokenizationError::invalid_digit(buf, cursor));
             }
+            buf += &exponent;
         }
-        if !rnormal {
-            return Err(TokenizationError::invalid_digit(buf, &cursor));
+        if at_dot(cursor) {
+            buf.push('.');
+            return Err(TokenizationError::invalid_digit(buf, cursor));
         }
+        let suffix = Self::get_suffix(cursor, chars);
         cursor.backward();
-        if hasdot {
-            Ok(Token::float_lit(buf, &cursor))
-        } else {
-            Ok(Token::int_lit(buf, &cursor))
+        match &*suffix {
+            "" if float => Ok(Token::float_lit(buf, cursor)),
+            "" => Ok(Token::int_lit(buf, cursor)),
+            "f32" | "f64" => Ok(Token::float_lit(buf + &suffix, cursor)),
+            suffix if !float && INT_SUFFIXES.contains(&suffix) => {
+                Ok(Token::int_lit(buf + suffix, cursor))
+            }
+            _ => Err(TokenizationError::invalid_digit(buf + &suffix, cursor)),
+        }
+    }
+    //digits valid in the radix, skipping the _ separators between them
+    fn get_digits(cursor: &mut Cursor, chars: &[char], radix: u32) -> String {
+        let mut digits = String::new();
+        while let Some(chr) = chars
+            .get(cursor.index())
+            .filter(|c| **c == '_' || c.is_digit(radix))
+        {
+            if *chr != '_' {
+                digits.push(*chr);
+            }
+            cursor.advance();
+        }
+        digits
+    }
+    //converts digits of any radix into decimal ones, without limiting their size
+    fn to_decimal(digits: &str, radix: u32) -> String {
+        //least significant digit first
+        let mut decimal = vec![0];
+        for digit in digits.chars().filter_map(|c| c.to_digit(radix)) {
+            let mut carry = digit;
+            for d in decimal.iter_mut() {
+                let value = *d * radix + carry;
+                *d = value % 10;
+                carry = value / 10;
+            }
+            while carry > 0 {
+                decimal.push(carry % 10);
+                carry /= 10;
+            }
         }
+        decimal
+            .iter()
+            .rev()
+            .map(|d| char::from_digit(*d, 10).unwrap())
+            .collect()
+    }
+    //type suffix written right after the digits of a literal, such as 1.5f64
+    fn get_suffix(cursor: &mut Cursor, chars: &[char]) -> String {
+        let mut suffix = String::new();
+        while let Some(chr) = chars.get(cursor.index()).filter(|c| c.is_alphanumeric()) {
+            suffix.push(*chr);
+            cursor.advance();
+        }
+        suffix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        let tokens = Tokenizer::new(source.to_string()).gen().unwrap();
+        tokens.into_iter().map(|token| token.kind).collect()
+    }
+
+    fn error(source: &str) -> TokenizationErrorKind {
+        Tokenizer::new(source.to_string()).gen().unwrap_err().kind
+    }
+
+    fn ident(name: &str) -> TokenKind {
+        TokenKind::Identifier(name.to_string())
+    }
+
+    fn int(lit: &str) -> TokenKind {
+        TokenKind::IntLit(lit.to_string())
+    }
+
+    fn float(lit: &str) -> TokenKind {
+        TokenKind::FloatLit(lit.to_string())
+    }
+
+    #[test]
+    fn decimal_literals() {
+        assert_eq!(kinds("1_000"), [int("1000")]);
+        assert_eq!(kinds("7u16 3isize"), [int("7u16"), int("3isize")]);
+        assert_eq!(kinds("1.5 1."), [float("1.5"), float("1.")]);
+        assert_eq!(kinds("1e3 2.5e-2f64"), [float("1e3"), float("2.5e-2f64")]);
+        assert_eq!(kinds("1..2"), [int("1"), TokenKind::DotDot, int("2")]);
+        assert!(matches!(
+            error("1.5f16"),
+            TokenizationErrorKind::InvalidDigit(_)
+        ));
+    }
+
+    #[test]
+    fn radix_literals() {
+        assert_eq!(kinds("0xff 0x_1F"), [int("255"), int("31")]);
+        assert_eq!(kinds("0b1010_1010u8"), [int("170u8")]);
+        assert_eq!(kinds("0o17i64"), [int("15i64")]);
+        assert!(matches!(
+            error("0x"),
+            TokenizationErrorKind::InvalidDigit(_)
+        ));
+        assert!(matches!(
+            error("0b12"),
+            TokenizationErrorKind::InvalidDigit(_)
+        ));
+    }
+
+    #[test]
+    fn nested_comments() {
+        assert_eq!(
+            kinds("a /* x /* y */ z */ b // c\nd"),
+            [ident("a"), ident("b"), ident("d")]
+        );
+        assert!(matches!(
+            error("/* x /* y */"),
+            TokenizationErrorKind::UnterminatedComment
+        ));
+    }
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(
+            kinds(r#""a\n\t\r\0\\\"\'\u{e9}""#),
+            [TokenKind::StrLit("a\n\t\r\0\\\"'é".to_string())]
+        );
+        assert_eq!(kinds(r"'\u{1F600}'"), [TokenKind::CharLit('😀')]);
+        assert!(matches!(
+            error(r#""\q""#),
+            TokenizationErrorKind::InvalidEscape(_)
+        ));
+        assert!(matches!(
+            error(r#""abc"#),
+            TokenizationErrorKind::UnterminatedString
+        ));
+    }
+
+    #[test]
+    fn raw_strings() {
+        assert_eq!(
+            kinds(r#"r"a\nb\""#),
+            [TokenKind::StrLit(r"a\nb\".to_string())]
+        );
+        assert_eq!(
+            kinds("r\"two\nlines\""),
+            [TokenKind::StrLit("two\nlines".to_string())]
+        );
+    }
+
+    #[test]
+    fn char_literals() {
+        assert_eq!(
+            kinds(r"'a' '\''"),
+            [TokenKind::CharLit('a'), TokenKind::CharLit('\'')]
+        );
+        assert!(matches!(error("''"), TokenizationErrorKind::EmptyChar));
+        assert!(matches!(
+            error("'ab'"),
+            TokenizationErrorKind::MultipleChars
+        ));
+        assert!(matches!(
+            error("'a"),
+            TokenizationErrorKind::UnterminatedChar
+        ));
+    }
+
+    #[test]
+    fn xid_identifiers() {
+        assert_eq!(
+            kinds("ñandú _x é1 x_ü 変数"),
+            [
+                ident("ñandú"),
+                ident("_x"),
+                ident("é1"),
+                ident("x_ü"),
+                ident("変数")
+            ]
+        );
+        assert_eq!(kinds("_ __"), [TokenKind::Underscore, ident("__")]);
+        assert!(matches!(
+            error("a€"),
+            TokenizationErrorKind::UnexpectedChar('€')
+        ));
+    }
+
+    #[test]
+    fn spans_are_byte_offsets() {
+        let source = "let ñ = \"é\"; /* ü */ ñ";
+        let tokens = Tokenizer::new(source.to_string()).gen().unwrap();
+        let slices: Vec<&str> = tokens
+            .iter()
+            .map(|token| &source[token.span().start..token.span().end])
+            .collect();
+        assert_eq!(slices, ["let", "ñ", "=", "\"é\"", ";", "ñ"]);
+    }
+
+    #[test]
+    fn identifiers_at_the_end_of_the_source() {
+        assert_eq!(kinds("x"), [TokenKind::Identifier("x".to_string())]);
+        assert_eq!(kinds("let _"), [TokenKind::Let, TokenKind::Underscore]);
+        assert_eq!(
+            kinds("a = b_1"),
+            [
+                TokenKind::Identifier("a".to_string()),
+                TokenKind::Operator(Operator::Eq),
+                TokenKind::Identifier("b_1".to_string()),
+            ]
+        );
     }
 }
@@ -7,27 +7,136 @@ pub enum Operator {
     Minus,
     Star,
     Bar,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
+    ShiftLeft,
+    ShiftRight,
+    PlusEq,
+    MinusEq,
+    StarEq,
+    BarEq,
+    PercentEq,
+    AmpersandEq,
+    PipeEq,
+    CaretEq,
+    ShiftLeftEq,
+    ShiftRightEq,
+    EqEq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    And,
+    Or,
+    Not,
+}
+impl Operator {
+    pub fn is_assignment(&self) -> bool {
+        *self == Operator::Eq || self.assignment_base().is_some()
+    }
+    //the operator applied by a compound assignment, such as + for +=
+    pub fn assignment_base(&self) -> Option<Operator> {
+        Some(match self {
+            Operator::PlusEq => Operator::Plus,
+            Operator::MinusEq => Operator::Minus,
+            Operator::StarEq => Operator::Star,
+            Operator::BarEq => Operator::Bar,
+            Operator::PercentEq => Operator::Percent,
+            Operator::AmpersandEq => Operator::Ampersand,
+            Operator::PipeEq => Operator::Pipe,
+            Operator::CaretEq => Operator::Caret,
+            Operator::ShiftLeftEq => Operator::ShiftLeft,
+            Operator::ShiftRightEq => Operator::ShiftRight,
+            _ => return None,
+        })
+    }
+    pub fn is_bitwise(&self) -> bool {
+        matches!(
+            self,
+            Operator::Ampersand
+                | Operator::Pipe
+                | Operator::Caret
+                | Operator::ShiftLeft
+                | Operator::ShiftRight
+        )
+    }
+    pub fn is_logical(&self) -> bool {
+        matches!(self, Operator::And | Operator::Or)
+    }
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Operator::EqEq
+                | Operator::NotEq
+                | Operator::Less
+                | Operator::LessEq
+                | Operator::Greater
+                | Operator::GreaterEq
+        )
+    }
 }
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TokenKind {
     Let,
+    Mut,
     Func,
+    If,
+    Else,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+    Return,
+    As,
+    //discards the value bound to it
+    Underscore,
     Identifier(String),
     IntLit(String),
     FloatLit(String),
+    BoolLit(bool),
+    StrLit(String),
+    CharLit(char),
     OpenParen,
     CloseParen,
     OpenBrace,
     CloseBrace,
     SemiColon,
     Colon,
+    Comma,
+    DotDot,
+    DotDotEq,
     Operator(Operator),
+    //text following a /// comment
+    DocComment(String),
+    //text following a //! comment
+    InnerDocComment(String),
     Eof,
 }
+//byte offsets into the source, the end is exclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+    //from the start of this span to the end of a later one
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+}
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Token {
     line: usize,
     column: usize,
+    span: Span,
     pub kind: TokenKind,
 }
 impl Token {
@@ -51,8 +160,12 @@ impl Token {
             kind,
             line: cursor.line(),
             column: cursor.column(),
+            span: Span::default(),
         }
     }
+    pub fn with_span(self, span: Span) -> Self {
+        Self { span, ..self }
+    }
     pub fn refkind(&self) -> &TokenKind {
         &self.kind
     }
@@ -62,4 +175,7 @@ impl Token {
     pub fn column(&self) -> usize {
         self.column
     }
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
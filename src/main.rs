@@ -1,19 +1,4 @@
-use std::{
-    io::{Read, Write},
-    path::Path,
-};
-
-fn get_file_content(path: &Path) -> Result<String, String> {
-    if let Ok(mut f) = std::fs::File::open(path) {
-        let mut buffer = String::new();
-        f.read_to_string(&mut buffer).unwrap();
-        Ok(buffer)
-    } else {
-        Err(format!(
-            "Encountered some error while trying to get file at location: {path:?}"
-        ))
-    }
-}
+use std::path::Path;
 
 fn print_help() {
     println!("--List of Commands--");